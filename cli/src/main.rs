@@ -1,12 +1,16 @@
+use std::collections::HashMap;
 use std::fmt;
 
 use anyhow::bail;
-use chrono::{DateTime, Utc};
-use chrono_tz::Tz;
+use chrono::{DateTime, NaiveTime, Timelike, Utc};
 use clap::Parser;
 use console::style;
 
-use libwhen::{get_time_of_day, InputExpr, LocationKind, TimeAtLocation};
+use libwhen::{
+    format_duration, format_military, get_time_of_day, DateOrder, DstPolicy, DurationExpr,
+    InputExpr, IntervalAtLocation, Language, LocationKind, ParseOptions, TimeAtLocation,
+    TimeDifference, TimeDirection, TimeOfDay, WeekStart, Zone,
+};
 
 /// A small utility to convert times from the command line.
 ///
@@ -21,6 +25,10 @@ use libwhen::{get_time_of_day, InputExpr, LocationKind, TimeAtLocation};
 /// translates it to toronto (airport).  It then prints out both
 /// timestamps on stdout with additional information.
 ///
+/// Durations can be calculated with "until <expr>", "since <expr>" and
+/// "between <expr> and <expr>".  Locations need "in" on each side, for
+/// instance "between 9am in vie and 6pm in yyz".
+///
 /// For more examples see https://github.com/mitsuhiko/when
 #[derive(Parser)]
 #[clap(version = clap::crate_version!(), max_term_width = 100)]
@@ -39,6 +47,83 @@ struct Cli {
     #[clap(long = "json")]
     json: bool,
 
+    /// output times in military notation with NATO zone letters (`1430Z`).
+    #[clap(long = "military")]
+    military: bool,
+
+    /// the order of day and month in numeric dates. Choices are `dmy`, `mdy`, `ymd`.
+    ///
+    /// If not supplied dotted dates (`16.10.`) are day first and other numeric
+    /// dates (`10/16`) are only accepted if they are unambiguous.  With `ymd`
+    /// dates without a year are month first unless dotted.
+    #[clap(long = "date-order", env = "WHEN_DATE_ORDER")]
+    date_order: Option<String>,
+
+    /// how to resolve local times at DST transitions. Choices are `earlier`,
+    /// `later`, `reject`, `shift-forward`.
+    ///
+    /// Ambiguous times (when the clock is turned back) and nonexistent times
+    /// (when the clock is turned forward) are by default resolved to the
+    /// earlier instant and shifted forward by the length of the gap.
+    #[clap(long = "dst-policy", env = "WHEN_DST_POLICY")]
+    dst_policy: Option<String>,
+
+    /// the first day of the week. Choices are `monday`, `sunday`.
+    ///
+    /// This is used for expressions like "start of next week".
+    #[clap(long = "week-start", env = "WHEN_WEEK_START")]
+    week_start: Option<String>,
+
+    /// the language of the expression. Choices are `en`, `de`, `fr`, `es`, `ja`.
+    ///
+    /// Localized keywords, month and weekday names and time formats like
+    /// "15 Uhr" or "15h30" are understood in addition to English.
+    #[clap(long = "language", env = "WHEN_LANGUAGE")]
+    language: Option<String>,
+
+    /// zones or locations to prefer for ambiguous timezone abbreviations.
+    ///
+    /// Abbreviations like `IST` or `CST` are used with more than one UTC
+    /// offset.  They default to the US, India, the UK and Australia, for
+    /// instance `--prefer-zone Asia/Jerusalem` picks Israel Standard Time
+    /// for `IST` instead.  Can be supplied multiple times or comma separated.
+    #[clap(
+        long = "prefer-zone",
+        env = "WHEN_PREFER_ZONES",
+        multiple_occurrences = true,
+        use_delimiter = true
+    )]
+    prefer_zones: Vec<String>,
+
+    /// the times of time-of-day words like "morning" or "tonight".
+    ///
+    /// Given as `period=HH:MM` for the periods `early_morning`, `morning`,
+    /// `late_morning`, `noon` (lunchtime), `afternoon`, `early_evening` (EOD,
+    /// COB), `evening` (tonight), `late_evening` and `night`.  The time has to
+    /// fall into the period.  Can be supplied multiple times or comma
+    /// separated.
+    #[clap(
+        long = "time-of-day",
+        env = "WHEN_TIMES_OF_DAY",
+        multiple_occurrences = true,
+        use_delimiter = true
+    )]
+    times_of_day: Vec<String>,
+
+    /// resolve bare times, weekdays and year-less dates to their next occurrence.
+    ///
+    /// Without this "5pm" is 5pm today even if that already passed and
+    /// "3rd of january" is in the current year.
+    #[clap(long = "next", conflicts_with = "past")]
+    next: bool,
+
+    /// resolve bare times, weekdays and year-less dates to their last occurrence.
+    ///
+    /// This is useful for log files where "Oct 16 14:03:22" always refers
+    /// to the past.
+    #[clap(long = "past")]
+    past: bool,
+
     /// returns a list of all known IANA/Olson timezones.
     #[clap(long = "list-timezones")]
     list_timezones: bool,
@@ -50,7 +135,7 @@ struct Cli {
     expr: Option<String>,
 }
 
-pub struct ZoneOffset(DateTime<Tz>);
+pub struct ZoneOffset(DateTime<Zone>);
 
 impl fmt::Display for ZoneOffset {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -64,21 +149,57 @@ impl fmt::Display for ZoneOffset {
     }
 }
 
-fn print_date(tod: &TimeAtLocation, now: DateTime<Utc>) {
+fn format_time(date: &DateTime<Zone>, military: bool) -> String {
+    if military {
+        format_military(date)
+    } else {
+        date.format("%H:%M:%S%.f").to_string()
+    }
+}
+
+fn print_date(
+    tod: &TimeAtLocation,
+    interval: Option<&IntervalAtLocation>,
+    now: DateTime<Utc>,
+    military: bool,
+) {
     let date = tod.datetime();
     let zone = tod.zone();
     let adjusted = date.with_timezone(&zone.tz());
     println!(
         "time: {} ({}; {})",
-        style(adjusted.format("%H:%M:%S")).bold().cyan(),
+        style(format_time(&adjusted, military)).bold().cyan(),
         tod.relative_to_human(now),
         get_time_of_day(adjusted),
     );
-    println!(
-        "date: {} ({})",
+    if let Some(interval) = interval {
+        let end = interval.end().datetime();
+        println!(
+            "until: {} ({} {}; {})",
+            style(format_time(&end, military)).bold().cyan(),
+            style(end.format("%Y-%m-%d")).yellow(),
+            style(end.format("%A")),
+            interval.duration_to_human(),
+        );
+    }
+    for dst_adjustment in tod
+        .dst_adjustment()
+        .into_iter()
+        .chain(interval.and_then(|x| x.end().dst_adjustment()))
+    {
+        println!("note: {} (DST transition)", style(dst_adjustment).red());
+    }
+    print!(
+        "date: {} ({}; {}; day {}",
         style(adjusted.format("%Y-%m-%d")).yellow(),
         style(adjusted.format("%A")),
+        tod.iso_week_date(),
+        tod.day_of_year(),
     );
+    if let Some(shift) = interval.map(|x| x.day_shift()).filter(|x| *x != 0) {
+        print!("; {}", style(format_day_shift(shift)).red());
+    }
+    println!(")");
     println!(
         "zone: {} ({})",
         style(zone.tz().name()).underlined(),
@@ -103,11 +224,38 @@ fn print_date(tod: &TimeAtLocation, now: DateTime<Utc>) {
     }
 }
 
+fn format_day_shift(shift: i64) -> String {
+    format!(
+        "{:+} {}",
+        shift,
+        if shift.abs() == 1 { "day" } else { "days" }
+    )
+}
+
+fn print_difference(diff: &TimeDifference, military: bool) {
+    println!(
+        "duration: {} ({})",
+        style(diff.duration_to_human()).bold().cyan(),
+        format_duration(diff.duration()),
+    );
+    for (label, t) in [("from", diff.from()), ("to", diff.to())] {
+        let date = t.datetime();
+        println!(
+            "{}: {} {} {} ({})",
+            label,
+            style(date.format("%Y-%m-%d")).yellow(),
+            format_time(&date, military),
+            date.format("%z"),
+            t.zone(),
+        );
+    }
+}
+
 fn list_timezones() -> Result<(), anyhow::Error> {
     let now = Utc::now();
     let mut zone_list = Vec::new();
     for zone in chrono_tz::TZ_VARIANTS {
-        let there = now.with_timezone(&zone);
+        let there = now.with_timezone(&Zone::from(zone));
         zone_list.push((zone, there));
     }
     zone_list.sort_by_key(|x| x.0.name());
@@ -133,26 +281,149 @@ pub fn execute() -> Result<(), anyhow::Error> {
         return list_timezones();
     }
 
-    let expr = InputExpr::parse(cli.expr.as_deref().unwrap_or("now"))?;
-    let timestamps = expr.process()?;
+    let date_order = match cli.date_order.as_deref() {
+        None | Some("") => None,
+        Some("dmy") => Some(DateOrder::Dmy),
+        Some("mdy") => Some(DateOrder::Mdy),
+        Some("ymd") => Some(DateOrder::Ymd),
+        Some(other) => bail!("unknown value for --date-order ({})", other),
+    };
+    let dst_policy = match cli.dst_policy.as_deref() {
+        None | Some("") | Some("shift-forward") => DstPolicy::ShiftForward,
+        Some("earlier") => DstPolicy::Earlier,
+        Some("later") => DstPolicy::Later,
+        Some("reject") => DstPolicy::Reject,
+        Some(other) => bail!("unknown value for --dst-policy ({})", other),
+    };
+    let week_start = match cli.week_start.as_deref() {
+        None | Some("") | Some("monday") => WeekStart::Monday,
+        Some("sunday") => WeekStart::Sunday,
+        Some(other) => bail!("unknown value for --week-start ({})", other),
+    };
+    let language = match cli.language.as_deref() {
+        None | Some("") | Some("en") => Language::English,
+        Some("de") => Language::German,
+        Some("fr") => Language::French,
+        Some("es") => Language::Spanish,
+        Some("ja") => Language::Japanese,
+        Some(other) => bail!("unknown value for --language ({})", other),
+    };
+    let mut times_of_day = HashMap::new();
+    for item in &cli.times_of_day {
+        let (period, time) = match item.split_once('=') {
+            Some(pair) => pair,
+            None => bail!(
+                "invalid value for --time-of-day ({}, expected period=HH:MM)",
+                item
+            ),
+        };
+        let period = match period.trim() {
+            "early_morning" => TimeOfDay::EarlyMorning,
+            "morning" => TimeOfDay::Morning,
+            "late_morning" => TimeOfDay::LateMorning,
+            "noon" => TimeOfDay::Noon,
+            "afternoon" => TimeOfDay::Afternoon,
+            "early_evening" => TimeOfDay::EarlyEvening,
+            "evening" => TimeOfDay::Evening,
+            "late_evening" => TimeOfDay::LateEvening,
+            "night" => TimeOfDay::Night,
+            other => bail!("unknown period for --time-of-day ({})", other),
+        };
+        let time = match NaiveTime::parse_from_str(time.trim(), "%H:%M") {
+            Ok(time) => time,
+            Err(_) => bail!("invalid time for --time-of-day ({})", time),
+        };
+        if TimeOfDay::from_hour(time.hour()) != period {
+            bail!(
+                "time for --time-of-day {} does not fall into {}",
+                item,
+                period
+            );
+        }
+        times_of_day.insert(period, time);
+    }
+    let direction = if cli.next {
+        TimeDirection::Future
+    } else if cli.past {
+        TimeDirection::Past
+    } else {
+        TimeDirection::Current
+    };
+    let options = ParseOptions {
+        date_order,
+        dst_policy,
+        direction,
+        prefer_zones: cli.prefer_zones,
+        week_start,
+        language,
+        times_of_day,
+    };
+
+    let input = cli.expr.as_deref().unwrap_or("now");
+    if let Some(expr) = DurationExpr::parse_with_options(input, &options)? {
+        let diff = expr.process()?;
+        if cli.json {
+            println!("{}", serde_json::to_string_pretty(&diff).unwrap());
+        } else if cli.short {
+            println!("{}", format_duration(diff.duration()));
+        } else {
+            print_difference(&diff, cli.military);
+        }
+        return Ok(());
+    }
+
+    let expr = InputExpr::parse_with_options(input, &options)?;
+    let intervals = expr.process_interval()?;
+    let is_interval = expr.is_interval();
 
     if cli.json {
-        println!("{}", serde_json::to_string_pretty(&timestamps).unwrap());
+        let output = if is_interval {
+            serde_json::to_string_pretty(&intervals)
+        } else {
+            serde_json::to_string_pretty(&intervals.iter().map(|x| x.start()).collect::<Vec<_>>())
+        };
+        println!("{}", output.unwrap());
     } else if cli.short {
-        for t in timestamps.iter() {
-            println!(
-                "{} ({})",
-                t.datetime().format("%Y-%m-%d %H:%M:%S %z"),
-                t.zone()
-            );
+        let format_short = |t: &TimeAtLocation| {
+            if cli.military {
+                format!(
+                    "{} {}",
+                    t.datetime().format("%Y-%m-%d"),
+                    format_military(&t.datetime())
+                )
+            } else {
+                t.datetime().format("%Y-%m-%d %H:%M:%S%.f %z").to_string()
+            }
+        };
+        for interval in intervals.iter() {
+            let t = interval.start();
+            if is_interval {
+                print!(
+                    "{} – {} ({}",
+                    format_short(t),
+                    format_short(interval.end()),
+                    t.zone()
+                );
+                if interval.day_shift() != 0 {
+                    print!("; {}", format_day_shift(interval.day_shift()));
+                }
+                println!(")");
+            } else {
+                println!("{} ({})", format_short(t), t.zone());
+            }
         }
     } else {
         let now = Utc::now();
-        for (idx, t) in timestamps.iter().enumerate() {
+        for (idx, interval) in intervals.iter().enumerate() {
             if idx > 0 {
                 println!();
             }
-            print_date(t, now);
+            print_date(
+                interval.start(),
+                Some(interval).filter(|_| is_interval),
+                now,
+                cli.military,
+            );
         }
     }
 
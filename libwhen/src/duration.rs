@@ -0,0 +1,112 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+
+/// A duration made up of calendar units and exact elapsed time.
+///
+/// Years are stored as months and weeks as days.  Months and days are
+/// calendar arithmetic: they move the local date in the timezone of the
+/// datetime and keep the wall clock time, so "in 2 days" at 9am is 9am
+/// again even if a DST transition happens in between.  Months are added
+/// before days and clamp to the end of the month (January 31st plus one
+/// month is the last day of February).  Seconds on the other hand are
+/// exact elapsed time and are added last.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CalendarDuration {
+    pub months: i64,
+    pub days: i64,
+    pub seconds: i64,
+}
+
+impl CalendarDuration {
+    /// Returns the duration pointing into the other direction.
+    pub fn negate(self) -> CalendarDuration {
+        CalendarDuration {
+            months: -self.months,
+            days: -self.days,
+            seconds: -self.seconds,
+        }
+    }
+
+    /// Creates a duration of calendar days.
+    pub fn days(days: i64) -> CalendarDuration {
+        CalendarDuration {
+            days,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a duration of exact seconds.
+    pub fn seconds(seconds: i64) -> CalendarDuration {
+        CalendarDuration {
+            seconds,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a duration of calendar months.
+    pub fn months(months: i64) -> CalendarDuration {
+        CalendarDuration {
+            months,
+            ..Default::default()
+        }
+    }
+
+    /// Adds two durations unit by unit.
+    pub fn checked_add(self, other: CalendarDuration) -> Option<CalendarDuration> {
+        Some(CalendarDuration {
+            months: self.months.checked_add(other.months)?,
+            days: self.days.checked_add(other.days)?,
+            seconds: self.seconds.checked_add(other.seconds)?,
+        })
+    }
+
+    /// True if the duration has months or days.
+    pub fn is_calendar(&self) -> bool {
+        self.months != 0 || self.days != 0
+    }
+
+    /// Adds the months and days of the duration to a local datetime.
+    ///
+    /// This keeps the wall clock time and ignores the exact part.
+    pub fn add_to_local(&self, local: NaiveDateTime) -> Option<NaiveDateTime> {
+        let date = add_months(local.date(), self.months)?
+            .checked_add_signed(checked_seconds(self.days.checked_mul(86400)?)?)?;
+        Some(date.and_time(local.time()))
+    }
+
+    /// Returns the exact part of the duration.
+    ///
+    /// This fails if the duration cannot be represented.
+    pub fn exact(&self) -> Option<Duration> {
+        checked_seconds(self.seconds)
+    }
+}
+
+/// Creates a duration from seconds unless it's out of the supported range.
+fn checked_seconds(seconds: i64) -> Option<Duration> {
+    if seconds.checked_abs()? <= i64::MAX / 1000 {
+        Some(Duration::seconds(seconds))
+    } else {
+        None
+    }
+}
+
+/// Adds months to a date, clamping the day to the end of the month.
+fn add_months(date: NaiveDate, months: i64) -> Option<NaiveDate> {
+    let total = (date.year() as i64 * 12 + date.month0() as i64).checked_add(months)?;
+    let year = i32::try_from(total.div_euclid(12)).ok()?;
+    let month = total.rem_euclid(12) as u32 + 1;
+    let day = date.day().min(days_in_month(year, month));
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+/// Returns the number of days in a month.
+pub(crate) fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|x| x.pred_opt())
+        .map_or(31, |x| x.day())
+}
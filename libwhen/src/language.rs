@@ -0,0 +1,775 @@
+use serde::Deserialize;
+
+/// The language of the keywords in an expression.
+///
+/// English is what the grammar understands natively.  For the other
+/// languages localized keywords, month and weekday names and time formats
+/// (`15 Uhr`, `15h30`, `15時30分`) are translated into English first.
+/// English keywords keep working in all languages as an expression that
+/// fails to parse translated is parsed as English.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Language {
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "de")]
+    German,
+    #[serde(rename = "fr")]
+    French,
+    #[serde(rename = "es")]
+    Spanish,
+    #[serde(rename = "ja")]
+    Japanese,
+}
+
+impl Default for Language {
+    fn default() -> Language {
+        Language::English
+    }
+}
+
+/// The keyword tables of a language.
+///
+/// All entries are lowercase and map to English keywords which must not be
+/// longer than the localized ones.
+struct Table {
+    /// Words and phrases with their English counterparts.
+    words: &'static [(&'static str, &'static str)],
+    /// The names of the months from January to December.
+    months: [&'static [&'static str]; 12],
+    /// The names of the weekdays from Monday to Sunday.
+    weekdays: [&'static [&'static str]; 7],
+    /// Qualifiers that follow a weekday (`lundi prochain`).
+    weekday_suffixes: &'static [(&'static str, &'static str)],
+    /// Words between a day and a month (`16 de octubre`).
+    date_particles: &'static [&'static str],
+    /// Words that follow a time to set the meridiem (`3 de la tarde`).
+    meridiem_suffixes: &'static [(&'static str, &'static str)],
+    /// Words after an hour that make it a time (`15 Uhr`).
+    clock_words: &'static [&'static str],
+    /// Words after an hour that make it a time if preceded by `at`
+    /// (`à 15 heures`).
+    at_clock_words: &'static [&'static str],
+    /// True if an `h` after an hour makes it a time (`15h30`).
+    hour_suffix: bool,
+    /// Words for `at` before a time and `in` before a location.
+    at_or_in: &'static [&'static str],
+    /// Localized names of places.
+    places: &'static [(&'static str, &'static str)],
+}
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// Translated words after which `15h` is a duration rather than a time.
+const RELATIVE_WORDS: [&str; 3] = ["in", "ago", "and"];
+
+const GERMAN: Table = Table {
+    words: &[
+        ("übermorgen", "in 2 days"),
+        ("vorgestern", "2d ago"),
+        ("morgen", "tmrw"),
+        ("heute", "today"),
+        ("gestern", "yd"),
+        ("jetzt", "now"),
+        ("mittag", "noon"),
+        ("mitternacht", "midnight"),
+        ("nächsten", "next"),
+        ("nächste", "next"),
+        ("kommenden", "next"),
+        ("kommende", "next"),
+        ("letzten", "last"),
+        ("letzte", "last"),
+        ("diesen", "this"),
+        ("diese", "this"),
+        ("um", "at"),
+        ("am", "on"),
+        ("vor", "ago"),
+        ("und", "and"),
+        ("bis", "to"),
+        ("von", ""),
+        ("jahren", "years"),
+        ("jahre", "years"),
+        ("jahr", "year"),
+        ("monaten", "months"),
+        ("monate", "months"),
+        ("monat", "month"),
+        ("wochen", "weeks"),
+        ("woche", "week"),
+        ("tagen", "days"),
+        ("tage", "days"),
+        ("tag", "day"),
+        ("stunden", "hours"),
+        ("stunde", "hour"),
+        ("std", "h"),
+        ("minuten", "minutes"),
+        ("minute", "minute"),
+        ("sekunden", "seconds"),
+        ("sekunde", "second"),
+    ],
+    months: [
+        &["januar", "jänner", "jan"],
+        &["februar", "feb"],
+        &["märz", "mär"],
+        &["april", "apr"],
+        &["mai"],
+        &["juni", "jun"],
+        &["juli", "jul"],
+        &["august", "aug"],
+        &["september", "sept", "sep"],
+        &["oktober", "okt"],
+        &["november", "nov"],
+        &["dezember", "dez"],
+    ],
+    weekdays: [
+        &["montag"],
+        &["dienstag"],
+        &["mittwoch"],
+        &["donnerstag"],
+        &["freitag"],
+        &["samstag", "sonnabend"],
+        &["sonntag"],
+    ],
+    weekday_suffixes: &[],
+    date_particles: &[],
+    meridiem_suffixes: &[],
+    clock_words: &["uhr"],
+    at_clock_words: &[],
+    hour_suffix: false,
+    at_or_in: &[],
+    places: &[("wien", "Vienna"), ("tokio", "Tokyo"), ("paris", "Paris")],
+};
+
+const FRENCH: Table = Table {
+    words: &[
+        ("après-demain", "in 2 days"),
+        ("avant-hier", "2d ago"),
+        ("demain", "tmrw"),
+        ("aujourd'hui", "today"),
+        ("aujourd’hui", "today"),
+        ("hier", "yd"),
+        ("maintenant", "now"),
+        ("midi", "noon"),
+        ("minuit", "0:00"),
+        ("prochain", "next"),
+        ("prochaine", "next"),
+        ("dernier", "last"),
+        ("dernière", "last"),
+        ("ce", "this"),
+        ("le", "on"),
+        ("dans", "in"),
+        ("en", "in"),
+        ("il y a", "ago"),
+        ("de", ""),
+        ("ans", "yrs"),
+        ("an", "yr"),
+        ("années", "years"),
+        ("année", "year"),
+        ("mois", "mos"),
+        ("semaines", "weeks"),
+        ("semaine", "week"),
+        ("jours", "days"),
+        ("jour", "day"),
+        ("heures", "hours"),
+        ("heure", "hour"),
+        ("secondes", "seconds"),
+        ("seconde", "second"),
+    ],
+    months: [
+        &["janvier", "janv"],
+        &["février", "févr"],
+        &["mars"],
+        &["avril", "avr"],
+        &["mai"],
+        &["juin"],
+        &["juillet", "juil"],
+        &["août"],
+        &["septembre", "sept"],
+        &["octobre", "oct"],
+        &["novembre", "nov"],
+        &["décembre", "déc"],
+    ],
+    weekdays: [
+        &["lundi"],
+        &["mardi"],
+        &["mercredi"],
+        &["jeudi"],
+        &["vendredi"],
+        &["samedi"],
+        &["dimanche"],
+    ],
+    weekday_suffixes: &[("prochain", "next"), ("dernier", "last")],
+    date_particles: &[],
+    meridiem_suffixes: &[
+        ("du matin", "am"),
+        ("de l'après-midi", "pm"),
+        ("de l’après-midi", "pm"),
+        ("du soir", "pm"),
+    ],
+    clock_words: &[],
+    at_clock_words: &["heures", "heure"],
+    hour_suffix: true,
+    at_or_in: &["à"],
+    places: &[
+        ("vienne", "Vienna"),
+        ("londres", "London"),
+        ("tokyo", "Tokyo"),
+    ],
+};
+
+const SPANISH: Table = Table {
+    words: &[
+        ("pasado mañana", "in 2 days"),
+        ("anteayer", "2d ago"),
+        ("mañana", "tmrw"),
+        ("hoy", "tdy"),
+        ("ayer", "yd"),
+        ("ahora", "now"),
+        ("mediodía", "noon"),
+        ("medianoche", "midnight"),
+        ("próximo", "next"),
+        ("próxima", "next"),
+        ("este", "this"),
+        ("esta", "this"),
+        ("a las", "at"),
+        ("a la", "at"),
+        ("el", "on"),
+        ("dentro de", "in"),
+        ("en", "in"),
+        ("hace", "ago"),
+        ("de", ""),
+        ("años", "years"),
+        ("año", "year"),
+        ("meses", "mos"),
+        ("mes", "mo"),
+        ("semanas", "weeks"),
+        ("semana", "week"),
+        ("días", "days"),
+        ("día", "day"),
+        ("horas", "hours"),
+        ("hora", "hour"),
+        ("minutos", "minutes"),
+        ("minuto", "minute"),
+        ("segundos", "seconds"),
+        ("segundo", "second"),
+    ],
+    months: [
+        &["enero", "ene"],
+        &["febrero", "feb"],
+        &["marzo"],
+        &["abril", "abr"],
+        &["mayo"],
+        &["junio", "jun"],
+        &["julio", "jul"],
+        &["agosto"],
+        &["septiembre", "setiembre", "sept", "sep"],
+        &["octubre", "oct"],
+        &["noviembre", "nov"],
+        &["diciembre", "dic"],
+    ],
+    weekdays: [
+        &["lunes"],
+        &["martes"],
+        &["miércoles", "miercoles"],
+        &["jueves"],
+        &["viernes"],
+        &["sábado", "sabado"],
+        &["domingo"],
+    ],
+    weekday_suffixes: &[
+        ("que viene", "next"),
+        ("próximo", "next"),
+        ("pasado", "last"),
+    ],
+    date_particles: &["de"],
+    meridiem_suffixes: &[
+        ("de la mañana", "am"),
+        ("de la madrugada", "am"),
+        ("de la tarde", "pm"),
+        ("de la noche", "pm"),
+    ],
+    clock_words: &[],
+    at_clock_words: &["horas", "hora"],
+    hour_suffix: true,
+    at_or_in: &[],
+    places: &[
+        ("viena", "Vienna"),
+        ("londres", "London"),
+        ("parís", "Paris"),
+        ("nueva york", "New York"),
+        ("tokio", "Tokyo"),
+    ],
+};
+
+const JAPANESE: Table = Table {
+    words: &[
+        ("明後日", "in 2 days"),
+        ("一昨日", "2d ago"),
+        ("明日", "tmrw"),
+        ("今日", "today"),
+        ("昨日", "yd"),
+        ("今", "now"),
+        ("正午", "noon"),
+        ("真夜中", "midnight"),
+        ("来週の", "next"),
+        ("来週", "next"),
+        ("次の", "next"),
+        ("先週の", "last"),
+        ("先週", "last"),
+        ("今週の", "this"),
+        ("今週", "this"),
+        ("の", ""),
+    ],
+    months: [&[], &[], &[], &[], &[], &[], &[], &[], &[], &[], &[], &[]],
+    weekdays: [
+        &["月曜日", "月曜"],
+        &["火曜日", "火曜"],
+        &["水曜日", "水曜"],
+        &["木曜日", "木曜"],
+        &["金曜日", "金曜"],
+        &["土曜日", "土曜"],
+        &["日曜日", "日曜"],
+    ],
+    weekday_suffixes: &[],
+    date_particles: &[],
+    meridiem_suffixes: &[],
+    clock_words: &[],
+    at_clock_words: &[],
+    hour_suffix: false,
+    at_or_in: &["に", "で"],
+    places: &[
+        ("ウィーン", "Vienna"),
+        ("東京", "Tokyo"),
+        ("ロンドン", "London"),
+        ("パリ", "Paris"),
+        ("ニューヨーク", "New York"),
+        ("ベルリン", "Berlin"),
+    ],
+};
+
+/// Japanese units of relative times with their English abbreviations.
+const JAPANESE_UNITS: [(&str, &str); 9] = [
+    ("時間", "h"),
+    ("日", "d"),
+    ("週間", "w"),
+    ("か月", "mo"),
+    ("ヶ月", "mo"),
+    ("カ月", "mo"),
+    ("年", "y"),
+    ("分", "m"),
+    ("秒", "s"),
+];
+
+impl Language {
+    fn table(self) -> Option<&'static Table> {
+        match self {
+            Language::English => None,
+            Language::German => Some(&GERMAN),
+            Language::French => Some(&FRENCH),
+            Language::Spanish => Some(&SPANISH),
+            Language::Japanese => Some(&JAPANESE),
+        }
+    }
+
+    /// Returns the English name of a localized place name.
+    pub(crate) fn place(self, name: &str) -> Option<&'static str> {
+        let name = name.to_lowercase();
+        self.table()?
+            .places
+            .iter()
+            .find(|x| x.0 == name)
+            .map(|x| x.1)
+    }
+
+    /// Translates an expression into English keywords.
+    ///
+    /// Localized words are replaced by English ones padded with spaces, so
+    /// the result has the same length as the input and every byte offset
+    /// refers to the same place in both.  This lets the parser take
+    /// locations and zones from the original expression.  Returns `None`
+    /// for English.
+    pub(crate) fn translate(self, expr: &str) -> Option<String> {
+        let table = self.table()?;
+        let mut translator = Translator {
+            table,
+            japanese: self == Language::Japanese,
+            out: String::with_capacity(expr.len()),
+        };
+        let mut pos = 0;
+        while let Some(c) = expr[pos..].chars().next() {
+            let at_word_start = !expr[..pos].chars().next_back().map_or(false, |c| {
+                if translator.japanese {
+                    c.is_ascii_alphanumeric()
+                } else {
+                    c.is_alphanumeric()
+                }
+            });
+            let replacement = if at_word_start {
+                translator.replace(&expr[pos..])
+            } else {
+                None
+            };
+            match replacement {
+                Some((len, english)) => {
+                    translator.out.push_str(&english);
+                    translator
+                        .out
+                        .extend(std::iter::repeat(' ').take(len - english.len()));
+                    pos += len;
+                }
+                None => {
+                    translator.out.push(c);
+                    pos += c.len_utf8();
+                }
+            }
+        }
+        debug_assert_eq!(translator.out.len(), expr.len());
+        Some(translator.out)
+    }
+}
+
+struct Translator {
+    table: &'static Table,
+    japanese: bool,
+    out: String,
+}
+
+impl Translator {
+    /// Finds a replacement at the start of the input.
+    ///
+    /// Returns the length of the replaced input and the English text which
+    /// is never longer than that.
+    fn replace(&self, input: &str) -> Option<(usize, String)> {
+        let rv = if self.japanese {
+            self.japanese_time(input)
+                .or_else(|| self.japanese_date(input))
+                .or_else(|| self.japanese_relative(input))
+                .or_else(|| self.word(input))
+        } else if input.starts_with(|c: char| c.is_ascii_digit()) {
+            self.date(input).or_else(|| self.time(input))
+        } else {
+            self.word(input)
+        };
+        rv.filter(|(len, english)| english.len() <= *len)
+    }
+
+    /// Returns the last word that was written.
+    fn previous_word(&self) -> &str {
+        self.out
+            .trim_end()
+            .rsplit(char::is_whitespace)
+            .next()
+            .unwrap_or("")
+    }
+
+    /// Matches the longest of the given words at the start of the input.
+    fn match_words<'t, I>(&self, input: &str, words: I) -> Option<(usize, &'t str)>
+    where
+        I: IntoIterator<Item = (&'t str, &'t str)>,
+    {
+        words
+            .into_iter()
+            .filter(|(word, _)| !word.is_empty() && self.starts_with_word(input, word))
+            .max_by_key(|(word, _)| word.len())
+            .map(|(word, english)| (word.len(), english))
+    }
+
+    /// Checks if the input starts with a word, ignoring case.
+    fn starts_with_word(&self, input: &str, word: &str) -> bool {
+        let candidate = match input.get(..word.len()) {
+            Some(candidate) => candidate,
+            None => return false,
+        };
+        candidate.to_lowercase() == word
+            && (self.japanese
+                || !input[word.len()..]
+                    .chars()
+                    .next()
+                    .map_or(false, char::is_alphanumeric))
+    }
+
+    fn month(&self, input: &str) -> Option<(usize, &'static str)> {
+        self.match_words(
+            input,
+            self.table
+                .months
+                .iter()
+                .zip(MONTHS.iter())
+                .flat_map(|(names, english)| names.iter().map(move |name| (*name, *english))),
+        )
+    }
+
+    fn weekday(&self, input: &str) -> Option<(usize, &'static str)> {
+        self.match_words(
+            input,
+            self.table
+                .weekdays
+                .iter()
+                .zip(WEEKDAYS.iter())
+                .flat_map(|(names, english)| names.iter().map(move |name| (*name, *english))),
+        )
+    }
+
+    /// Translates a keyword, month or weekday.
+    fn word(&self, input: &str) -> Option<(usize, String)> {
+        if let Some((len, weekday)) = self.weekday(input) {
+            let (ws, rest) = skip_whitespace(&input[len..]);
+            if ws > 0 {
+                if let Some((suffix_len, qualifier)) =
+                    self.match_words(rest, self.table.weekday_suffixes.iter().copied())
+                {
+                    return Some((len + ws + suffix_len, format!("{} {}", qualifier, weekday)));
+                }
+            }
+            return Some((len, weekday.to_string()));
+        }
+        if let Some((len, _)) =
+            self.match_words(input, self.table.at_or_in.iter().map(|word| (*word, "")))
+        {
+            let (_, rest) = skip_whitespace(&input[len..]);
+            let english = if rest.is_empty() {
+                ""
+            } else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+                "at"
+            } else {
+                "in"
+            };
+            return Some((len, english.to_string()));
+        }
+        let month = self.month(input);
+        let word = self.match_words(input, self.table.words.iter().copied());
+        match (month, word) {
+            (Some(month), Some(word)) if word.0 > month.0 => Some((word.0, word.1.to_string())),
+            (Some((len, english)), _) | (None, Some((len, english))) => {
+                Some((len, english.to_string()))
+            }
+            (None, None) => None,
+        }
+    }
+
+    /// Translates a day followed by a month name (`16. Oktober`, `1er mai`).
+    fn date(&self, input: &str) -> Option<(usize, String)> {
+        let (day, mut len) = parse_digits(input, 2)?;
+        let rest = &input[len..];
+        if let Some(suffix) = [".", "er"].iter().find(|x| rest.starts_with(*x)) {
+            len += suffix.len();
+        }
+        let (ws, mut rest) = skip_whitespace(&input[len..]);
+        if ws == 0 {
+            return None;
+        }
+        len += ws;
+        for particle in self.table.date_particles {
+            if self.starts_with_word(rest, particle) {
+                let (ws, after) = skip_whitespace(&rest[particle.len()..]);
+                if ws > 0 {
+                    len += particle.len() + ws;
+                    rest = after;
+                }
+                break;
+            }
+        }
+        let (month_len, month) = self.month(rest)?;
+        if !(1..=31).contains(&day) {
+            return None;
+        }
+        Some((len + month_len, format!("{:02} {}", day, month)))
+    }
+
+    /// Translates localized time formats (`15 Uhr 30`, `15h30`).
+    fn time(&self, input: &str) -> Option<(usize, String)> {
+        let (hour, mut len) = parse_digits(input, 2)?;
+        let mut minute = None;
+        if let Some(rest) = input[len..]
+            .strip_prefix(':')
+            .or_else(|| input[len..].strip_prefix('.'))
+        {
+            let (value, minute_len) = parse_digits(rest, 2)?;
+            if minute_len != 2 {
+                return None;
+            }
+            minute = Some(value);
+            len += 1 + minute_len;
+        }
+
+        let (ws, rest) = skip_whitespace(&input[len..]);
+        let mut formatted = false;
+        if let Some((word_len, _)) = self.match_words(rest, clock_words(self.table.clock_words)) {
+            len += ws + word_len;
+            formatted = true;
+            if minute.is_none() {
+                let (ws, rest) = skip_whitespace(&input[len..]);
+                if ws > 0 {
+                    if let Some((value, minute_len)) = parse_digits(rest, 2) {
+                        minute = Some(value);
+                        len += ws + minute_len;
+                    }
+                }
+            }
+        } else if self.table.hour_suffix
+            && ws == 0
+            && minute.is_none()
+            && rest.starts_with(['h', 'H'])
+        {
+            let rest = &rest[1..];
+            match parse_digits(rest, 2) {
+                Some((value, 2)) => {
+                    minute = Some(value);
+                    len += 3;
+                }
+                Some(_) => return None,
+                None if RELATIVE_WORDS.contains(&self.previous_word()) => return None,
+                None => len += 1,
+            }
+            formatted = true;
+        } else if self.previous_word() == "at" && ws > 0 {
+            if let Some((word_len, _)) =
+                self.match_words(rest, clock_words(self.table.at_clock_words))
+            {
+                len += ws + word_len;
+                formatted = true;
+            }
+        }
+
+        let mut meridiem = "";
+        let (ws, rest) = skip_whitespace(&input[len..]);
+        if ws > 0 {
+            if let Some((suffix_len, value)) =
+                self.match_words(rest, self.table.meridiem_suffixes.iter().copied())
+            {
+                len += ws + suffix_len;
+                meridiem = value;
+                formatted = true;
+            }
+        }
+
+        if !formatted
+            || hour > if meridiem.is_empty() { 23 } else { 12 }
+            || minute.map_or(false, |x| x > 59)
+        {
+            return None;
+        }
+        Some((len, format_time(hour, minute, meridiem, len)))
+    }
+
+    /// Translates `15時30分`, `15時半` and `午後3時`.
+    fn japanese_time(&self, input: &str) -> Option<(usize, String)> {
+        let mut len = 0;
+        let mut meridiem = "";
+        for (word, value) in [("午前", "am"), ("午後", "pm")] {
+            if let Some(rest) = input.strip_prefix(word) {
+                let (ws, _) = skip_whitespace(rest);
+                len = word.len() + ws;
+                meridiem = value;
+            }
+        }
+        let (hour, hour_len) = parse_digits(&input[len..], 2)?;
+        len += hour_len;
+        len += input[len..].strip_prefix('時').map(|_| '時'.len_utf8())?;
+        if input[len..].starts_with('間') {
+            return None;
+        }
+        let mut minute = None;
+        if input[len..].starts_with('半') {
+            minute = Some(30);
+            len += '半'.len_utf8();
+        } else if let Some((value, minute_len)) = parse_digits(&input[len..], 2) {
+            if let Some(rest) = input[len + minute_len..].strip_prefix('分') {
+                if !rest.starts_with(['後', '前']) {
+                    minute = Some(value);
+                    len += minute_len + '分'.len_utf8();
+                }
+            }
+        }
+        if hour > if meridiem.is_empty() { 23 } else { 12 } || minute.map_or(false, |x| x > 59) {
+            return None;
+        }
+        Some((len, format_time(hour, minute, meridiem, len)))
+    }
+
+    /// Translates `2026年10月16日` and `10月16日`.
+    fn japanese_date(&self, input: &str) -> Option<(usize, String)> {
+        let (first, first_len) = parse_digits(input, 4)?;
+        let mut len = first_len;
+        let year = if first_len == 4 {
+            len += input[len..].strip_prefix('年').map(|_| '年'.len_utf8())?;
+            Some(first)
+        } else {
+            None
+        };
+        let month = if year.is_some() {
+            let (month, month_len) = parse_digits(&input[len..], 2)?;
+            len += month_len;
+            month
+        } else {
+            first
+        };
+        len += input[len..].strip_prefix('月').map(|_| '月'.len_utf8())?;
+        let (day, day_len) = parse_digits(&input[len..], 2)?;
+        len += day_len;
+        len += input[len..].strip_prefix('日').map(|_| '日'.len_utf8())?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        Some((
+            len,
+            match year {
+                Some(year) => format!("{:04}-{:02}-{:02}", year, month, day),
+                None => format!("{} {:02}", MONTHS[month as usize - 1], day),
+            },
+        ))
+    }
+
+    /// Translates `3時間後` and `2日前`.
+    fn japanese_relative(&self, input: &str) -> Option<(usize, String)> {
+        let (count, mut len) = parse_digits(input, 4)?;
+        let (unit_len, unit) = JAPANESE_UNITS
+            .iter()
+            .find(|(word, _)| input[len..].starts_with(word))
+            .map(|(word, unit)| (word.len(), *unit))?;
+        len += unit_len;
+        let rest = &input[len..];
+        let english = if rest.starts_with('後') {
+            format!("in {}{}", count, unit)
+        } else if rest.starts_with('前') {
+            format!("{}{} ago", count, unit)
+        } else {
+            return None;
+        };
+        Some((len + '後'.len_utf8(), english))
+    }
+}
+
+/// Formats a translated time, leaving out the minutes if they do not fit
+/// with room for a space after the time (`15時に東京`).
+fn format_time(hour: u32, minute: Option<u32>, meridiem: &str, len: usize) -> String {
+    let rv = format!("{}:{:02}{}", hour, minute.unwrap_or(0), meridiem);
+    if rv.len() < len || minute.is_some() {
+        rv
+    } else {
+        format!("{}{}", hour, meridiem)
+    }
+}
+
+/// Pairs clock words with an empty replacement for matching.
+fn clock_words(
+    words: &'static [&'static str],
+) -> impl Iterator<Item = (&'static str, &'static str)> {
+    words.iter().map(|word| (*word, ""))
+}
+
+/// Parses up to `max` ASCII digits and returns the value and their length.
+fn parse_digits(input: &str, max: usize) -> Option<(u32, usize)> {
+    let len = input.bytes().take_while(u8::is_ascii_digit).count();
+    if len == 0 || len > max {
+        return None;
+    }
+    Some((input[..len].parse().ok()?, len))
+}
+
+/// Skips leading whitespace and returns its length and the rest.
+fn skip_whitespace(input: &str) -> (usize, &str) {
+    let rest = input.trim_start();
+    (input.len() - rest.len(), rest)
+}
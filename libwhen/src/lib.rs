@@ -4,10 +4,18 @@
 //! Using this crate directly is not recommended as it's not maintained with a stable
 //! API interface.  It primarily exists so that it can be compiled to web assembly
 //! independently of the CLI tool.
+mod duration;
+mod language;
 mod location;
 mod parser;
 mod utils;
+mod zone;
 
+pub use self::language::Language;
 pub use self::location::{find_zone, Location, LocationKind, ZoneRef};
-pub use self::parser::{InputExpr, TimeAtLocation};
-pub use self::utils::{get_time_of_day, TimeOfDay};
+pub use self::parser::{
+    DateOrder, DateParseError, DurationExpr, InputExpr, IntervalAtLocation, ParseOptions,
+    TimeAtLocation, TimeDifference, TimeDirection, WeekStart,
+};
+pub use self::utils::{format_duration, format_military, get_time_of_day, TimeOfDay};
+pub use self::zone::{DstAdjustment, DstPolicy, Zone, ZoneOffset};
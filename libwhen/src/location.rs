@@ -1,7 +1,11 @@
 use std::borrow::Cow;
+use std::cmp::Reverse;
 use std::fmt;
 
-use chrono_tz::Tz;
+use chrono::{Duration, FixedOffset, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetName, Tz};
+
+use crate::zone::{parse_offset, Zone};
 
 /// The type of location.
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -27,6 +31,7 @@ pub struct Location {
 #[derive(Debug, Clone, Copy)]
 pub enum ZoneRef {
     Tz(Tz),
+    Offset(FixedOffset),
     Location(&'static Location),
 }
 
@@ -53,10 +58,12 @@ impl ZoneRef {
     ///
     /// For actual timezones that can be the IANA name, for cities
     /// and airports this will be the actual name of the location.
-    pub fn name(&self) -> &str {
+    /// Fixed offsets are named after the offset (eg: `UTC+02:00`).
+    pub fn name(&self) -> Cow<'static, str> {
         match self {
-            ZoneRef::Tz(tz) => tz.name(),
-            ZoneRef::Location(loc) => loc.name,
+            ZoneRef::Tz(tz) => Cow::Borrowed(tz.name()),
+            ZoneRef::Offset(offset) => Zone::Fixed(*offset).name(),
+            ZoneRef::Location(loc) => Cow::Borrowed(loc.name),
         }
     }
 
@@ -66,7 +73,7 @@ impl ZoneRef {
     /// at UTC+0.
     pub fn is_utc(&self) -> bool {
         matches!(
-            self.tz().name(),
+            &*self.tz().name(),
             "Universal"
                 | "UTC"
                 | "UCT"
@@ -81,7 +88,7 @@ impl ZoneRef {
     /// Returns the kind of location.
     pub fn kind(&self) -> LocationKind {
         match self {
-            ZoneRef::Tz(_) => LocationKind::Timezone,
+            ZoneRef::Tz(_) | ZoneRef::Offset(_) => LocationKind::Timezone,
             ZoneRef::Location(loc) => loc.kind,
         }
     }
@@ -89,7 +96,7 @@ impl ZoneRef {
     /// If this zone reference points to a country, returns the country name.
     pub fn country(&self) -> Option<&str> {
         match self {
-            ZoneRef::Tz(_) => None,
+            ZoneRef::Tz(_) | ZoneRef::Offset(_) => None,
             ZoneRef::Location(loc) => COUNTRIES
                 .binary_search_by_key(&loc.country, |x| x.0)
                 .ok()
@@ -102,24 +109,65 @@ impl ZoneRef {
     /// For the US for instance this can be the name of the US state.
     pub fn admin_code(&self) -> Option<&str> {
         match self {
-            ZoneRef::Tz(_) => None,
+            ZoneRef::Tz(_) | ZoneRef::Offset(_) => None,
             ZoneRef::Location(loc) => loc.admin_code,
         }
     }
 
-    /// Returns a `chrono_tz` timezone object.
-    pub fn tz(&self) -> Tz {
+    /// Returns the timezone object.
+    pub fn tz(&self) -> Zone {
         match self {
-            ZoneRef::Tz(tz) => *tz,
-            ZoneRef::Location(loc) => loc.tz,
+            ZoneRef::Tz(tz) => Zone::Named(*tz),
+            ZoneRef::Offset(offset) => Zone::Fixed(*offset),
+            ZoneRef::Location(loc) => Zone::Named(loc.tz),
         }
     }
 }
 
+/// Timezones that use an abbreviation with the same UTC offset.
+#[derive(Debug, Clone)]
+pub(crate) struct AbbreviationCandidate {
+    /// The offset the zones have while the abbreviation is in use.
+    pub offset: FixedOffset,
+    /// The zones, the most commonly referenced one first.
+    pub zones: Vec<Tz>,
+}
+
+/// The result of looking up a zone by name.
+pub(crate) enum ZoneLookup {
+    /// The name refers to a single zone.
+    Zone(ZoneRef),
+    /// The name is a timezone abbreviation (eg: `PST`).
+    Abbreviation(Vec<AbbreviationCandidate>),
+}
+
 include!(concat!(env!("OUT_DIR"), "/locations.rs"));
 
 /// Tries to locate a zone by name
+///
+/// Timezone abbreviations (eg: `PST`) are resolved if they are in use by a
+/// single UTC offset around the current time.
 pub fn find_zone(name: &str) -> Option<ZoneRef> {
+    match lookup_zone(name, &Utc::now().naive_utc(), false)? {
+        ZoneLookup::Zone(rv) => Some(rv),
+        ZoneLookup::Abbreviation(candidates) if candidates.len() == 1 => {
+            Some(ZoneRef::Tz(candidates[0].zones[0]))
+        }
+        ZoneLookup::Abbreviation(_) => None,
+    }
+}
+
+/// Looks up a zone by name and reports all candidates for abbreviations.
+///
+/// Abbreviations are resolved for the offsets in use around `near`.  Three
+/// letter airport codes take precedence over abbreviations (`IST` is the
+/// airport of Istanbul) unless `prefer_abbreviation` is set as it is for a
+/// zone attached to a time (`noon IST`).
+pub(crate) fn lookup_zone(
+    name: &str,
+    near: &NaiveDateTime,
+    prefer_abbreviation: bool,
+) -> Option<ZoneLookup> {
     let name = if name.eq_ignore_ascii_case("local") {
         match localzone::get_local_zone() {
             Some(zone) => Cow::Owned(zone),
@@ -129,11 +177,31 @@ pub fn find_zone(name: &str) -> Option<ZoneRef> {
         Cow::Borrowed(name)
     };
 
+    if name.eq_ignore_ascii_case("z") {
+        return Some(ZoneLookup::Zone(ZoneRef::Tz(Tz::UTC)));
+    }
+    if let Some(offset) = parse_offset(&name) {
+        return Some(ZoneLookup::Zone(ZoneRef::Offset(offset)));
+    }
+
+    // legacy zones named like an abbreviation (`CET`) that observe DST are
+    // resolved as abbreviations so that they are checked for the date.
     let tz_name = name.replace(" ", "_");
-    for tz in chrono_tz::TZ_VARIANTS {
-        if tz.name().eq_ignore_ascii_case(&tz_name) {
-            return Some(ZoneRef::Tz(tz));
-        }
+    if let Some(tz) = chrono_tz::TZ_VARIANTS
+        .iter()
+        .find(|x| x.name().eq_ignore_ascii_case(&tz_name))
+    {
+        let candidate = find_abbreviation(&name, near)
+            .into_iter()
+            .find(|x| x.zones.contains(tz))
+            .filter(|_| observes_dst(tz, near));
+        return Some(match candidate {
+            Some(candidate) => ZoneLookup::Abbreviation(vec![AbbreviationCandidate {
+                offset: candidate.offset,
+                zones: vec![*tz],
+            }]),
+            None => ZoneLookup::Zone(ZoneRef::Tz(*tz)),
+        });
     }
 
     for delim in [',', ' '] {
@@ -145,7 +213,7 @@ pub fn find_zone(name: &str) -> Option<ZoneRef> {
                     && (x.country.eq_ignore_ascii_case(code)
                         || x.admin_code.map_or(false, |x| x.eq_ignore_ascii_case(code)))
             }) {
-                return Some(ZoneRef::Location(rv));
+                return Some(ZoneLookup::Zone(ZoneRef::Location(rv)));
             }
         }
     }
@@ -155,18 +223,97 @@ pub fn find_zone(name: &str) -> Option<ZoneRef> {
         .find(|x| x.name.eq_ignore_ascii_case(&name))
         .map(ZoneRef::Location)
     {
-        return Some(loc);
+        return Some(ZoneLookup::Zone(loc));
     }
 
-    if name.len() == 3 {
-        if let Some(loc) = LOCATIONS
+    let candidates = find_abbreviation(&name, near);
+    let airport = if name.len() == 3 {
+        LOCATIONS
             .iter()
             .find(|x| x.aliases.iter().any(|x| x.eq_ignore_ascii_case(&name)))
             .map(ZoneRef::Location)
-        {
-            return Some(loc);
+    } else {
+        None
+    };
+    match airport {
+        Some(loc) if !prefer_abbreviation || candidates.is_empty() => Some(ZoneLookup::Zone(loc)),
+        _ if !candidates.is_empty() => Some(ZoneLookup::Abbreviation(candidates)),
+        _ => None,
+    }
+}
+
+/// True if the zone changes its offset in the year around `near`.
+fn observes_dst(tz: &Tz, near: &NaiveDateTime) -> bool {
+    let offset = tz.offset_from_utc_datetime(near).fix();
+    (-6..=6).any(|months| {
+        near.checked_add_signed(Duration::days(months * 30))
+            .map_or(false, |utc| {
+                tz.offset_from_utc_datetime(&utc).fix() != offset
+            })
+    })
+}
+
+/// Zones that are preferred for abbreviations that are used with more than
+/// one UTC offset unless something else is preferred explicitly.  `CST` for
+/// instance is US Central Time and not China Standard Time and `IST` is
+/// Indian Standard Time.
+pub(crate) const DEFAULT_PREFERRED_ZONES: &[Tz] = &[
+    Tz::America__Los_Angeles,
+    Tz::America__Denver,
+    Tz::America__Chicago,
+    Tz::America__New_York,
+    Tz::America__Halifax,
+    Tz::Asia__Kolkata,
+    Tz::Europe__London,
+    Tz::Australia__Sydney,
+];
+
+/// Finds all timezones that use an abbreviation around the given time.
+///
+/// The abbreviations come from tzdata and are collected from the offsets
+/// of every zone over a year centered on `near`, so both the standard and
+/// the daylight saving time abbreviations are found.
+fn find_abbreviation(abbrev: &str, near: &NaiveDateTime) -> Vec<AbbreviationCandidate> {
+    // numeric abbreviations (`+03`) are handled as offsets
+    if !(2..=5).contains(&abbrev.len()) || !abbrev.bytes().all(|x| x.is_ascii_alphabetic()) {
+        return Vec::new();
+    }
+
+    let mut rv: Vec<AbbreviationCandidate> = Vec::new();
+    for tz in chrono_tz::TZ_VARIANTS {
+        let offset = (-6..=6).find_map(|months| {
+            let utc = near.checked_add_signed(Duration::days(months * 30))?;
+            let offset = tz.offset_from_utc_datetime(&utc);
+            if offset.abbreviation().eq_ignore_ascii_case(abbrev) {
+                Some(offset.fix())
+            } else {
+                None
+            }
+        });
+        if let Some(offset) = offset {
+            match rv.iter_mut().find(|x| x.offset == offset) {
+                Some(candidate) => candidate.zones.push(tz),
+                None => rv.push(AbbreviationCandidate {
+                    offset,
+                    zones: vec![tz],
+                }),
+            }
         }
     }
 
-    None
+    // zones that currently use the abbreviation come first, after that the
+    // ones with more known locations as they are the more canonical ones.
+    let popularity = |tz: &Tz| LOCATIONS.iter().filter(|x| x.tz == *tz).count();
+    let in_effect = |tz: &Tz| {
+        tz.offset_from_utc_datetime(near)
+            .abbreviation()
+            .eq_ignore_ascii_case(abbrev)
+    };
+    for candidate in rv.iter_mut() {
+        candidate
+            .zones
+            .sort_by_key(|tz| (Reverse(in_effect(tz)), Reverse(popularity(tz))));
+    }
+    rv.sort_by_key(|x| Reverse(x.zones.iter().map(popularity).sum::<usize>()));
+    rv
 }
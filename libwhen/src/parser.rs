@@ -1,26 +1,44 @@
+use std::collections::HashMap;
 use std::fmt;
-use std::ops::Add;
 
-use chrono::{DateTime, Datelike, Duration, NaiveDateTime, TimeZone, Timelike, Utc};
-use chrono_humanize::HumanTime;
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Timelike,
+    Utc, Weekday,
+};
+use chrono_humanize::{Accuracy, HumanTime, Tense};
 use chrono_tz::Tz;
 use pest::error::ErrorVariant;
 use pest::iterators::Pair;
 use pest::Parser;
 use pest_derive::Parser;
 use serde::ser::SerializeMap;
-use serde::{Serialize, Serializer};
+use serde::{Deserialize, Serialize, Serializer};
 
-use crate::location::{find_zone, LocationKind, ZoneRef};
-use crate::utils::get_time_of_day;
+use crate::duration::{days_in_month, CalendarDuration};
+use crate::language::Language;
+use crate::location::{
+    find_zone, lookup_zone, LocationKind, ZoneLookup, ZoneRef, DEFAULT_PREFERRED_ZONES,
+};
+use crate::utils::{format_duration, get_time_of_day, TimeOfDay};
+use crate::zone::{
+    localize, military_zone, parse_offset, DstAdjustment, DstPolicy, Localized, Zone,
+};
 
 /// Represents a parsing error.
 #[derive(Debug)]
 pub enum DateParseError {
-    Parser(pest::error::Error<Rule>),
+    Parser(Box<pest::error::Error<Rule>>),
     Garbage(String),
     OutOfRange(&'static str),
     MissingLocation(String),
+    AmbiguousDate(String),
+    InvalidDate(String),
+    AmbiguousTime(String),
+    NonexistentTime(String),
+    ConflictingZone(String, String),
+    AmbiguousAbbreviation(String, Vec<String>),
+    InactiveAbbreviation(String, String),
+    UnsupportedTarget(String),
 }
 
 impl std::error::Error for DateParseError {}
@@ -73,6 +91,51 @@ impl fmt::Display for DateParseError {
             DateParseError::MissingLocation(loc) => {
                 write!(f, "unknown timezone '{}'", loc)
             }
+            DateParseError::AmbiguousDate(date) => {
+                write!(
+                    f,
+                    "ambiguous date '{}' (could be day-month or month-day, set a date order)",
+                    date
+                )
+            }
+            DateParseError::InvalidDate(reason) => {
+                write!(f, "invalid date ({})", reason)
+            }
+            DateParseError::AmbiguousTime(local) => {
+                write!(f, "ambiguous local time {} (DST transition)", local)
+            }
+            DateParseError::NonexistentTime(local) => {
+                write!(f, "nonexistent local time {} (DST transition)", local)
+            }
+            DateParseError::ConflictingZone(zone, location) => {
+                write!(
+                    f,
+                    "timezone '{}' conflicts with location '{}'",
+                    zone, location
+                )
+            }
+            DateParseError::AmbiguousAbbreviation(abbrev, candidates) => {
+                write!(
+                    f,
+                    "ambiguous timezone abbreviation '{}' (could be {}; set a preferred zone)",
+                    abbrev,
+                    candidates.join(", ")
+                )
+            }
+            DateParseError::InactiveAbbreviation(abbrev, reason) => {
+                write!(
+                    f,
+                    "timezone abbreviation '{}' not in effect ({})",
+                    abbrev, reason
+                )
+            }
+            DateParseError::UnsupportedTarget(location) => {
+                write!(
+                    f,
+                    "cannot convert a duration to location '{}' (remove the '->')",
+                    location
+                )
+            }
         }
     }
 }
@@ -81,24 +144,135 @@ impl fmt::Display for DateParseError {
 #[grammar = "date_grammar.pest"]
 struct DateParser;
 
+/// The order of the components in numeric dates.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DateOrder {
+    /// Day first (`16.10.2021`)
+    Dmy,
+    /// Month first (`10/16/2021`)
+    Mdy,
+    /// Year first (`2021/10/16`), dates without a year are month first
+    /// (`10/16`) unless dotted
+    Ymd,
+}
+
+/// Controls which occurrence unqualified times and dates resolve to.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeDirection {
+    /// Bare times are on the current day and year-less dates in the
+    /// current year.  This is the default.
+    Current,
+    /// Bare times, weekdays and year-less dates resolve to their next
+    /// occurrence (now or later).
+    Future,
+    /// Bare times, weekdays and year-less dates resolve to their most
+    /// recent occurrence (now or earlier).
+    Past,
+}
+
+impl Default for TimeDirection {
+    fn default() -> TimeDirection {
+        TimeDirection::Current
+    }
+}
+
+impl TimeDirection {
+    /// Checks if a local time is on the right side of the current time.
+    fn accepts(self, local: &NaiveDateTime, now: &NaiveDateTime) -> bool {
+        match self {
+            TimeDirection::Current => true,
+            TimeDirection::Future => local >= now,
+            TimeDirection::Past => local <= now,
+        }
+    }
+}
+
+/// The first day of the week for week boundaries.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    /// Weeks start on Monday (ISO 8601).  This is the default.
+    Monday,
+    /// Weeks start on Sunday.
+    Sunday,
+}
+
+impl Default for WeekStart {
+    fn default() -> WeekStart {
+        WeekStart::Monday
+    }
+}
+
+impl WeekStart {
+    fn weekday(self) -> Weekday {
+        match self {
+            WeekStart::Monday => Weekday::Mon,
+            WeekStart::Sunday => Weekday::Sun,
+        }
+    }
+}
+
+/// Options that control how an expression is parsed and evaluated.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ParseOptions {
+    /// The order of day and month in numeric dates.
+    ///
+    /// If not set, dotted dates are day first and all other numeric dates
+    /// are only accepted if they are unambiguous.  Dotted dates stay day
+    /// first for year first order.  Dates that start with a four digit year
+    /// are always read as year, month and day.
+    pub date_order: Option<DateOrder>,
+    /// How local times at DST transitions are resolved.
+    pub dst_policy: DstPolicy,
+    /// Which occurrence bare times and year-less dates resolve to.
+    pub direction: TimeDirection,
+    /// Zones or locations to prefer for ambiguous timezone abbreviations.
+    ///
+    /// For instance `Asia/Jerusalem` picks Israel Standard Time for `IST`.
+    /// Without a matching preference abbreviations that are in use with
+    /// different UTC offsets fall back to common zones (US, India, UK and
+    /// Australia) and are rejected otherwise.
+    pub prefer_zones: Vec<String>,
+    /// The first day of the week for `start of week` and `end of week`.
+    pub week_start: WeekStart,
+    /// The language of keywords and month and weekday names.
+    pub language: Language,
+    /// The times that time-of-day words like `morning` resolve to.
+    ///
+    /// Periods that are not set use [`TimeOfDay::default_time`].  `lunchtime`
+    /// uses noon, `tonight` the evening and `EOD` and `COB` the early evening.
+    /// A time has to fall into its period.
+    pub times_of_day: HashMap<TimeOfDay, NaiveTime>,
+}
+
 /// Represents a human readable date expression
 #[derive(Debug)]
 pub struct InputExpr<'a> {
     time_spec: Option<TimeSpec>,
     date_spec: Option<DateSpec>,
+    end_time: Option<TimeSpec>,
+    offset: CalendarDuration,
+    rounding: Option<Rounding>,
     locations: Vec<&'a str>,
+    source_zone: Option<ZoneRef>,
+    zone_suffix: Option<&'a str>,
+    options: ParseOptions,
 }
 
 /// A tuple of time and location.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TimeAtLocation {
-    datetime: DateTime<Tz>,
+    datetime: DateTime<Zone>,
     zone_ref: ZoneRef,
+    dst_adjustment: Option<DstAdjustment>,
 }
 
 impl TimeAtLocation {
     /// Returns the timestamp in the given location.
-    pub fn datetime(&self) -> DateTime<Tz> {
+    pub fn datetime(&self) -> DateTime<Zone> {
         self.datetime
     }
 
@@ -109,16 +283,14 @@ impl TimeAtLocation {
 
     /// Human readable relative date.
     pub fn relative_to_human<Tz: TimeZone>(&self, now: DateTime<Tz>) -> String {
+        // truncate to the minute on the timestamps as the local times might
+        // not be unique around DST transitions.
+        let truncate = |ts: i64| ts - ts.rem_euclid(60);
         format!(
             "{:#}",
-            HumanTime::from(
-                self.datetime
-                    .with_second(0)
-                    .unwrap()
-                    .with_nanosecond(0)
-                    .unwrap()
-                    .signed_duration_since(now.with_second(0).unwrap().with_nanosecond(0).unwrap())
-            )
+            HumanTime::from(Duration::seconds(
+                truncate(self.datetime.timestamp()) - truncate(now.timestamp())
+            ))
         )
     }
 
@@ -126,9 +298,38 @@ impl TimeAtLocation {
     pub fn zone(&self) -> ZoneRef {
         self.zone_ref
     }
+
+    /// If the requested local time fell into a DST transition returns
+    /// how it was adjusted.
+    pub fn dst_adjustment(&self) -> Option<DstAdjustment> {
+        self.dst_adjustment
+    }
+
+    /// Returns the ISO 8601 week number (1 to 53).
+    ///
+    /// Note that the first and last days of a year can be in a week of the
+    /// neighboring year, see [`iso_week_date`](Self::iso_week_date).
+    pub fn iso_week(&self) -> u32 {
+        self.datetime.iso_week().week()
+    }
+
+    /// Returns the ISO 8601 weekday number (1 is Monday, 7 is Sunday).
+    pub fn weekday_number(&self) -> u32 {
+        self.datetime.weekday().number_from_monday()
+    }
+
+    /// Returns the day of the year (1 to 366).
+    pub fn day_of_year(&self) -> u32 {
+        self.datetime.ordinal()
+    }
+
+    /// Returns the ISO 8601 week date (eg: `2026-W42-5`).
+    pub fn iso_week_date(&self) -> String {
+        self.datetime.format("%G-W%V-%u").to_string()
+    }
 }
 
-impl<'a> Serialize for TimeAtLocation {
+impl Serialize for TimeAtLocation {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
@@ -137,17 +338,285 @@ impl<'a> Serialize for TimeAtLocation {
         let now = Utc::now();
         m.serialize_entry("datetime", &self.datetime)?;
         m.serialize_entry("time_of_day", &get_time_of_day(self.datetime))?;
+        m.serialize_entry("iso_week_date", &self.iso_week_date())?;
+        m.serialize_entry("iso_week", &self.iso_week())?;
+        m.serialize_entry("weekday_number", &self.weekday_number())?;
+        m.serialize_entry("day_of_year", &self.day_of_year())?;
         m.serialize_entry("relative_to_now_sec", &self.relative_to(now).num_seconds())?;
         m.serialize_entry("relative_to_now_human", &self.relative_to_human(now))?;
         m.serialize_entry("timezone", &SerializeZone(&self.zone_ref, &self.datetime))?;
         if self.zone_ref.kind() != LocationKind::Timezone {
             m.serialize_entry("location", &SerializeLocation(&self.zone_ref))?;
         }
+        if let Some(dst_adjustment) = self.dst_adjustment {
+            m.serialize_entry("dst_adjustment", &dst_adjustment)?;
+        }
+        m.end()
+    }
+}
+
+/// A time range at a location.
+#[derive(Debug, Clone)]
+pub struct IntervalAtLocation {
+    start: TimeAtLocation,
+    end: TimeAtLocation,
+    day_shift: i64,
+}
+
+impl IntervalAtLocation {
+    fn convert(zone_ref: ZoneRef, start: &DateTime<Zone>, end: &DateTime<Zone>) -> Self {
+        let datetime = start.with_timezone(&zone_ref.tz());
+        IntervalAtLocation {
+            day_shift: datetime
+                .naive_local()
+                .date()
+                .signed_duration_since(start.naive_local().date())
+                .num_days(),
+            start: TimeAtLocation {
+                datetime,
+                zone_ref,
+                dst_adjustment: None,
+            },
+            end: TimeAtLocation {
+                datetime: end.with_timezone(&zone_ref.tz()),
+                zone_ref,
+                dst_adjustment: None,
+            },
+        }
+    }
+
+    /// Returns the start of the range.
+    pub fn start(&self) -> &TimeAtLocation {
+        &self.start
+    }
+
+    /// Returns the end of the range.
+    pub fn end(&self) -> &TimeAtLocation {
+        &self.end
+    }
+
+    /// Returns the number of days the start date is shifted against the
+    /// source location.
+    ///
+    /// For instance a day in Vienna starts on the day before in San
+    /// Francisco which is a shift of `-1`.
+    pub fn day_shift(&self) -> i64 {
+        self.day_shift
+    }
+
+    /// Returns the length of the range.
+    pub fn duration(&self) -> Duration {
+        self.end.datetime.signed_duration_since(self.start.datetime)
+    }
+
+    /// Human readable length of the range.
+    pub fn duration_to_human(&self) -> String {
+        HumanTime::from(self.duration()).to_text_en(Accuracy::Precise, Tense::Present)
+    }
+}
+
+impl Serialize for IntervalAtLocation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut m = serializer.serialize_map(None)?;
+        m.serialize_entry("start", &self.start)?;
+        m.serialize_entry("end", &self.end)?;
+        m.serialize_entry("day_shift", &self.day_shift)?;
+        m.serialize_entry("duration_sec", &self.duration().num_seconds())?;
+        m.serialize_entry("duration_human", &self.duration_to_human())?;
+        m.end()
+    }
+}
+
+/// A query for the time between two instants.
+///
+/// This is `until <expr>`, `since <expr>` or `between <expr> and <expr>`
+/// where each side is an [`InputExpr`] with its own location.
+#[derive(Debug)]
+pub enum DurationExpr<'a> {
+    Until(InputExpr<'a>),
+    Since(InputExpr<'a>),
+    Between(InputExpr<'a>, Box<InputExpr<'a>>),
+}
+
+impl<'a> DurationExpr<'a> {
+    /// Parses a duration query from a string.
+    ///
+    /// Returns `None` if the string is not a duration query and should
+    /// be parsed as [`InputExpr`] instead.
+    pub fn parse(value: &'a str) -> Result<Option<DurationExpr<'a>>, DateParseError> {
+        DurationExpr::parse_with_options(value, &ParseOptions::default())
+    }
+
+    /// Parses a duration query from a string with the given options.
+    ///
+    /// Unless a direction is set bare times and dates resolve to their next
+    /// occurrence for `until` and their last one for `since`.  Each side of
+    /// `between` needs `in` before its location (`9am in vienna`).  Target
+    /// locations (`-> tokyo`) are rejected as a duration has no zone.
+    pub fn parse_with_options(
+        value: &'a str,
+        options: &ParseOptions,
+    ) -> Result<Option<DurationExpr<'a>>, DateParseError> {
+        let value = value.trim();
+        let (keyword, rest) = match value.find(char::is_whitespace) {
+            Some(idx) => (&value[..idx], value[idx..].trim_start()),
+            None => return Ok(None),
+        };
+        let with_direction = |direction| {
+            let mut options = options.clone();
+            if options.direction == TimeDirection::Current {
+                options.direction = direction;
+            }
+            options
+        };
+        let parse_side = |value, options| {
+            let expr = parse_input(value, options)?;
+            match expr.to_locations().first() {
+                Some(location) => Err(DateParseError::UnsupportedTarget(location.to_string())),
+                None => Ok(expr),
+            }
+        };
+        if keyword.eq_ignore_ascii_case("until") {
+            parse_side(rest, &with_direction(TimeDirection::Future))
+                .map(|x| Some(DurationExpr::Until(x)))
+        } else if keyword.eq_ignore_ascii_case("since") {
+            parse_side(rest, &with_direction(TimeDirection::Past))
+                .map(|x| Some(DurationExpr::Since(x)))
+        } else if keyword.eq_ignore_ascii_case("between") {
+            // relative times can contain "and" as well ("in 1 hour and 30
+            // minutes") so the first split where both sides parse wins.
+            let mut first_err = None;
+            for (idx, _) in rest.match_indices(char::is_whitespace) {
+                let start = &rest[..idx];
+                if start.ends_with(char::is_whitespace) {
+                    continue;
+                }
+                let end = rest[idx..].trim_start();
+                let end = match end.get(..3) {
+                    Some(and)
+                        if and.eq_ignore_ascii_case("and")
+                            && end[3..].starts_with(char::is_whitespace) =>
+                    {
+                        end[3..].trim_start()
+                    }
+                    _ => continue,
+                };
+                match parse_side(start, options)
+                    .and_then(|start| parse_side(end, options).map(|end| (start, end)))
+                {
+                    Ok((start, end)) => {
+                        return Ok(Some(DurationExpr::Between(start, Box::new(end))))
+                    }
+                    Err(err) => {
+                        first_err.get_or_insert(err);
+                    }
+                }
+            }
+            Err(first_err.unwrap_or_else(|| DateParseError::Garbage(rest.to_string())))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Resolves both sides of the query.
+    ///
+    /// The current time used for `until` and `since` is in the zone of the
+    /// other side.
+    pub fn process(&self) -> Result<TimeDifference, DateParseError> {
+        self.process_at(current_time())
+    }
+
+    /// Resolves both sides of the query relative to `now`.
+    fn process_at(&self, now: DateTime<Utc>) -> Result<TimeDifference, DateParseError> {
+        let current = |other: &TimeAtLocation| TimeAtLocation {
+            datetime: now.with_timezone(&other.zone_ref.tz()),
+            zone_ref: other.zone_ref,
+            dst_adjustment: None,
+        };
+        let (from, to) = match self {
+            DurationExpr::Until(expr) => {
+                let mut to = expr.resolve_instant(now)?;
+                // until a day that already started is until its end
+                if expr.is_whole_day() && to.datetime < now {
+                    if let Some((end, dst_adjustment)) = expr.resolve_end(&to.datetime)? {
+                        to = TimeAtLocation {
+                            datetime: end,
+                            dst_adjustment,
+                            ..to
+                        };
+                    }
+                }
+                (current(&to), to)
+            }
+            DurationExpr::Since(expr) => {
+                let from = expr.resolve_instant(now)?;
+                let to = current(&from);
+                (from, to)
+            }
+            DurationExpr::Between(start, end) => {
+                (start.resolve_instant(now)?, end.resolve_instant(now)?)
+            }
+        };
+        Ok(TimeDifference { from, to })
+    }
+}
+
+/// The signed duration between two instants.
+#[derive(Debug, Clone)]
+pub struct TimeDifference {
+    from: TimeAtLocation,
+    to: TimeAtLocation,
+}
+
+impl TimeDifference {
+    /// Returns the first instant.
+    pub fn from(&self) -> &TimeAtLocation {
+        &self.from
+    }
+
+    /// Returns the second instant.
+    pub fn to(&self) -> &TimeAtLocation {
+        &self.to
+    }
+
+    /// Returns the duration from the first to the second instant.
+    ///
+    /// This is negative if the second instant is before the first.
+    pub fn duration(&self) -> Duration {
+        self.to.datetime.signed_duration_since(self.from.datetime)
+    }
+
+    /// Human readable duration, prefixed with a minus sign if negative.
+    pub fn duration_to_human(&self) -> String {
+        let duration = self.duration();
+        let rv = HumanTime::from(duration).to_text_en(Accuracy::Precise, Tense::Present);
+        if duration < Duration::zero() {
+            format!("-{}", rv)
+        } else {
+            rv
+        }
+    }
+}
+
+impl Serialize for TimeDifference {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut m = serializer.serialize_map(None)?;
+        m.serialize_entry("from", &self.from)?;
+        m.serialize_entry("to", &self.to)?;
+        m.serialize_entry("duration_sec", &self.duration().num_seconds())?;
+        m.serialize_entry("duration_exact", &format_duration(self.duration()))?;
+        m.serialize_entry("duration_human", &self.duration_to_human())?;
         m.end()
     }
 }
 
-pub struct SerializeZone<'a>(&'a ZoneRef, &'a DateTime<Tz>);
+pub struct SerializeZone<'a>(&'a ZoneRef, &'a DateTime<Zone>);
 
 impl<'a> Serialize for SerializeZone<'a> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -155,7 +624,7 @@ impl<'a> Serialize for SerializeZone<'a> {
         S: Serializer,
     {
         let mut m = serializer.serialize_map(None)?;
-        m.serialize_entry("name", self.0.tz().name())?;
+        m.serialize_entry("name", &self.0.tz().name())?;
         m.serialize_entry("abbrev", &self.1.format("%Z").to_string())?;
         m.serialize_entry("utc_offset", &self.1.format("%z").to_string())?;
         m.end()
@@ -170,7 +639,7 @@ impl<'a> Serialize for SerializeLocation<'a> {
         S: Serializer,
     {
         let mut m = serializer.serialize_map(None)?;
-        m.serialize_entry("name", self.0.name())?;
+        m.serialize_entry("name", &self.0.name())?;
         if let Some(admin_code) = self.0.admin_code() {
             m.serialize_entry("admin_code", &admin_code)?;
         }
@@ -184,55 +653,124 @@ impl<'a> Serialize for SerializeLocation<'a> {
 impl<'a> InputExpr<'a> {
     /// Parses an expression from a string.
     pub fn parse(value: &'a str) -> Result<InputExpr<'a>, DateParseError> {
-        parse_input(value)
+        parse_input(value, &ParseOptions::default())
+    }
+
+    /// Parses an expression from a string with the given options.
+    pub fn parse_with_options(
+        value: &'a str,
+        options: &ParseOptions,
+    ) -> Result<InputExpr<'a>, DateParseError> {
+        parse_input(value, options)
     }
 
     /// Returns the location if available.
+    ///
+    /// If the zone is given by the timestamp itself there is no source
+    /// location and all locations are targets.
     pub fn location(&self) -> Option<&str> {
-        self.locations.get(0).copied()
+        match self.source_zone {
+            Some(_) => None,
+            None => self.locations.first().copied(),
+        }
     }
 
     /// Returns the target locations if available.
     pub fn to_locations(&self) -> &[&str] {
-        self.locations.get(1..).unwrap_or_default()
+        match self.source_zone {
+            Some(_) => &self.locations,
+            None => self.locations.get(1..).unwrap_or_default(),
+        }
     }
 
     /// Is this relative time?
     pub fn is_relative(&self) -> bool {
-        matches!(self.time_spec, None | Some(TimeSpec::Rel { .. }))
-            || matches!(self.date_spec, Some(DateSpec::Rel { .. }))
+        matches!(
+            self.time_spec,
+            None | Some(TimeSpec::Rel(..)) | Some(TimeSpec::Snap(..))
+        ) || matches!(
+            self.date_spec,
+            Some(
+                DateSpec::Rel(..)
+                    | DateSpec::Weekday { .. }
+                    | DateSpec::Boundary { .. }
+                    | DateSpec::NthWeekday {
+                        month: MonthRef::Rel(..),
+                        ..
+                    }
+            )
+        )
+    }
+
+    /// Is this a time range (`9am-5pm`)?
+    pub fn is_interval(&self) -> bool {
+        self.end_time.is_some()
+    }
+
+    /// Is this a whole day from 00:00 to 24:00 (`tomorrow`, `17.10`)?
+    fn is_whole_day(&self) -> bool {
+        matches!(
+            (&self.time_spec, &self.end_time),
+            (Some(start), Some(end)) if start.is_midnight() && end.is_midnight()
+        )
     }
 
     /// Resolves the expression into all referenced locations.
+    ///
+    /// For time ranges this is the start of the range.
     pub fn process(&self) -> Result<Vec<TimeAtLocation>, DateParseError> {
-        let zone_ref = self.location().unwrap_or("local");
-        let from_zone = find_zone(zone_ref)
-            .ok_or_else(|| DateParseError::MissingLocation(zone_ref.to_string()))?;
-        let now = Utc::now().with_timezone(&from_zone.tz());
-        let from = self.apply(now)?;
+        Ok(self
+            .process_interval()?
+            .into_iter()
+            .map(|x| x.start)
+            .collect())
+    }
 
-        let mut rv = vec![TimeAtLocation {
-            datetime: from,
-            zone_ref: from_zone,
+    /// Resolves the expression into intervals for all referenced locations.
+    ///
+    /// If the expression is not a time range start and end are the same.
+    pub fn process_interval(&self) -> Result<Vec<IntervalAtLocation>, DateParseError> {
+        self.process_interval_at(current_time())
+    }
+
+    /// Resolves the expression into intervals relative to `now`.
+    fn process_interval_at(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<IntervalAtLocation>, DateParseError> {
+        let start = self.resolve_source(now)?;
+        let from_zone = start.zone_ref;
+        let end = self
+            .resolve_end(&start.datetime)?
+            .unwrap_or((start.datetime, None));
+        let (from, dst_adjustment) = self.shift((start.datetime, start.dst_adjustment))?;
+        let (until, end_dst_adjustment) = self.shift(end)?;
+
+        let mut rv = vec![IntervalAtLocation {
+            start: TimeAtLocation {
+                datetime: from,
+                zone_ref: from_zone,
+                dst_adjustment,
+            },
+            end: TimeAtLocation {
+                datetime: until,
+                zone_ref: from_zone,
+                dst_adjustment: end_dst_adjustment,
+            },
+            day_shift: 0,
         }];
 
         for to_zone_ref in self.to_locations() {
-            let to_zone = find_zone(to_zone_ref)
-                .ok_or_else(|| DateParseError::MissingLocation(to_zone_ref.to_string()))?;
+            let (to_zone, to_abbrev) = self.find_zone(to_zone_ref, &from.naive_utc())?;
             let to = from.with_timezone(&to_zone.tz());
-            rv.push(TimeAtLocation {
-                datetime: to,
-                zone_ref: to_zone,
-            });
+            check_abbreviation(to_abbrev, &to)?;
+            rv.push(IntervalAtLocation::convert(to_zone, &from, &until));
         }
 
         if rv.len() == 1 {
             if let Some(to_zone) = find_zone("local") {
-                if to_zone.tz().name() != from_zone.tz().name() {
-                    rv.push(TimeAtLocation {
-                        datetime: from.with_timezone(&to_zone.tz()),
-                        zone_ref: to_zone,
-                    });
+                if to_zone.tz() != from_zone.tz() {
+                    rv.push(IntervalAtLocation::convert(to_zone, &from, &until));
                 }
             }
         }
@@ -240,70 +778,409 @@ impl<'a> InputExpr<'a> {
         Ok(rv)
     }
 
+    /// Resolves the expression including the offset in the source location.
+    fn resolve_instant(&self, now: DateTime<Utc>) -> Result<TimeAtLocation, DateParseError> {
+        let rv = self.resolve_source(now)?;
+        let (datetime, dst_adjustment) = self.shift((rv.datetime, rv.dst_adjustment))?;
+        Ok(TimeAtLocation {
+            datetime,
+            dst_adjustment,
+            ..rv
+        })
+    }
+
+    /// Resolves the expression in the source location relative to `now`.
+    ///
+    /// This is the anchor without the offset.
+    fn resolve_source(&self, now: DateTime<Utc>) -> Result<TimeAtLocation, DateParseError> {
+        let (from_zone, from_abbrev) = match self.source_zone {
+            Some(zone) => (zone, None),
+            None => {
+                // abbreviations depend on the date which is estimated in UTC
+                let near = self
+                    .resolve(now.with_timezone(&Zone::Named(Tz::UTC)))
+                    .map_or(now.naive_utc(), |x| x.0.naive_utc());
+                self.find_zone(self.location().unwrap_or("local"), &near)?
+            }
+        };
+        let (from, dst_adjustment) = self.resolve(now.with_timezone(&from_zone.tz()))?;
+        check_abbreviation(from_abbrev, &from)?;
+
+        // a zone on the time is fine as long as it agrees with the location
+        if let Some(zone_suffix) = self.zone_suffix.filter(|x| Some(*x) != self.location()) {
+            let (zone, abbrev) = self.find_zone(zone_suffix, &from.naive_utc())?;
+            let there = from.with_timezone(&zone.tz());
+            if there.offset().fix() != from.offset().fix() {
+                return Err(DateParseError::ConflictingZone(
+                    zone_suffix.to_string(),
+                    self.location().unwrap_or("local").to_string(),
+                ));
+            }
+            check_abbreviation(abbrev, &there)?;
+        }
+
+        Ok(TimeAtLocation {
+            datetime: from,
+            zone_ref: from_zone,
+            dst_adjustment,
+        })
+    }
+
+    /// Resolves the end of a time range relative to its start.
+    ///
+    /// An end time that is not after the start is on the next day so that
+    /// `10pm-2am` spans midnight.
+    fn resolve_end(&self, start: &DateTime<Zone>) -> Result<Option<Localized>, DateParseError> {
+        let (hour, minute, second, nanosecond) = match self.end_time {
+            Some(TimeSpec::Abs {
+                hour,
+                minute,
+                second,
+                nanosecond,
+            }) => (hour, minute, second, nanosecond),
+            _ => return Ok(None),
+        };
+        let start_local = start.naive_local();
+        let mut local = start_local
+            .date()
+            .and_hms_nano_opt(hour as u32, minute as u32, second as u32, nanosecond)
+            .ok_or(DateParseError::OutOfRange("time"))?;
+        if local <= start_local {
+            local = CalendarDuration::days(1)
+                .add_to_local(local)
+                .ok_or(DateParseError::OutOfRange("time"))?;
+        }
+        check_range(&local)?;
+        self.localize(&start.timezone(), &local).map(Some)
+    }
+
+    /// Moves a resolved time by the offset of the expression (`+ 3h30m`)
+    /// and rounds it afterwards.
+    ///
+    /// As with relative times days and larger units are calendar arithmetic
+    /// in the zone of the time whereas smaller units are exact.  Rounding is
+    /// done on the wall clock of the zone.
+    fn shift(&self, (mut rv, mut adjustment): Localized) -> Result<Localized, DateParseError> {
+        if self.offset.is_calendar() {
+            let local = self
+                .offset
+                .add_to_local(rv.naive_local())
+                .ok_or(DateParseError::OutOfRange("offset"))?;
+            check_range(&local)?;
+            let (shifted, shifted_adjustment) = self.localize(&rv.timezone(), &local)?;
+            rv = shifted;
+            adjustment = shifted_adjustment.or(adjustment);
+        }
+        rv = add_exact(rv, &self.offset)?;
+        if let Some(rounding) = self.rounding {
+            let local = rounding
+                .apply(rv.naive_local())
+                .ok_or(DateParseError::OutOfRange("rounding"))?;
+            check_range(&local)?;
+            let (rounded, rounded_adjustment) = self.localize(&rv.timezone(), &local)?;
+            rv = rounded;
+            adjustment = rounded_adjustment.or(adjustment);
+        }
+        Ok((rv, adjustment))
+    }
+
+    /// Converts a local time into the zone according to the DST policy.
+    fn localize(&self, zone: &Zone, local: &NaiveDateTime) -> Result<Localized, DateParseError> {
+        localize(zone, local, self.options.dst_policy).map_err(|err| {
+            let local = format!("{} in {}", local, zone.name());
+            match err {
+                DstAdjustment::Ambiguous => DateParseError::AmbiguousTime(local),
+                DstAdjustment::Nonexistent => DateParseError::NonexistentTime(local),
+            }
+        })
+    }
+
+    /// Resolves a location of the expression for a time near `near`.
+    ///
+    /// If the location is a timezone abbreviation it's returned as well so
+    /// that it can be checked against the resolved time.
+    fn find_zone(
+        &self,
+        name: &'a str,
+        near: &NaiveDateTime,
+    ) -> Result<(ZoneRef, Option<&'a str>), DateParseError> {
+        let prefer_abbreviation = self.zone_suffix == Some(name);
+        let candidates = match lookup_zone(name, near, prefer_abbreviation) {
+            Some(ZoneLookup::Zone(rv)) => return Ok((rv, None)),
+            Some(ZoneLookup::Abbreviation(candidates)) => candidates,
+            None => return Err(DateParseError::MissingLocation(name.to_string())),
+        };
+        for preferred in &self.options.prefer_zones {
+            if let Some(Zone::Named(tz)) = find_zone(preferred).map(|x| x.tz()) {
+                if candidates.iter().any(|x| x.zones.contains(&tz)) {
+                    return Ok((ZoneRef::Tz(tz), Some(name)));
+                }
+            }
+        }
+        if let [candidate] = &candidates[..] {
+            return Ok((ZoneRef::Tz(candidate.zones[0]), Some(name)));
+        }
+        for tz in DEFAULT_PREFERRED_ZONES {
+            if candidates.iter().any(|x| x.zones.contains(tz)) {
+                return Ok((ZoneRef::Tz(*tz), Some(name)));
+            }
+        }
+        Err(DateParseError::AmbiguousAbbreviation(
+            name.to_string(),
+            candidates
+                .iter()
+                .map(|x| format!("{} ({})", x.zones[0].name(), x.offset))
+                .collect(),
+        ))
+    }
+
     /// Applies the expression to a current reference date.
-    pub fn apply(&self, mut date: DateTime<Tz>) -> Result<DateTime<Tz>, DateParseError> {
+    ///
+    /// Days, weeks, months and years are calendar arithmetic in the timezone
+    /// of the reference date and keep the wall clock time ("9am in 2 days" is
+    /// 9am even across a DST change), whereas hours, minutes and seconds are
+    /// exact elapsed time ("in 48 hours" can land on a different wall time).
+    ///
+    /// Local times that fall into a DST transition are resolved according to
+    /// the [`DstPolicy`] of the parse options.
+    pub fn apply(&self, date: DateTime<Zone>) -> Result<DateTime<Zone>, DateParseError> {
+        self.resolve(date).and_then(|x| self.shift(x)).map(|x| x.0)
+    }
+
+    fn resolve(&self, date: DateTime<Zone>) -> Result<Localized, DateParseError> {
+        let now = date.naive_local();
+        // a whole day is only on the wrong side of now once it is over
+        let reference = if self.is_whole_day() {
+            now.date().and_hms(0, 0, 0)
+        } else {
+            now
+        };
+        let direction = self.options.direction;
+        let week_start = self.options.week_start;
+        let mut local = now;
         match self.time_spec {
             Some(TimeSpec::Abs {
                 hour,
                 minute,
                 second,
+                nanosecond,
             }) => {
-                date = date
-                    .with_hour(hour as u32)
-                    .unwrap()
-                    .with_minute(minute as u32)
-                    .unwrap()
-                    .with_second(second as u32)
-                    .unwrap();
-            }
-            Some(TimeSpec::Rel {
-                hours,
-                minutes,
-                seconds,
-            }) => {
-                date = date.add(Duration::hours(hours as i64));
-                date = date.add(Duration::minutes(minutes as i64));
-                date = date.add(Duration::seconds(seconds as i64));
+                local = local
+                    .date()
+                    .and_hms_nano_opt(hour as u32, minute as u32, second as u32, nanosecond)
+                    .ok_or(DateParseError::OutOfRange("time"))?;
+            }
+            Some(TimeSpec::Rel(duration)) => {
+                if !duration.is_calendar() {
+                    return Ok((add_exact(date, &duration)?, None));
+                }
+                local = duration
+                    .add_to_local(local)
+                    .ok_or(DateParseError::OutOfRange("relative time"))?;
+            }
+            Some(TimeSpec::Snap(rounding)) => {
+                local = rounding
+                    .apply(local)
+                    .ok_or(DateParseError::OutOfRange("time"))?;
             }
             None => {}
         }
         match self.date_spec {
             Some(DateSpec::Abs { day, month, year }) => {
-                date = date
-                    .with_day(day as u32)
-                    .ok_or(DateParseError::OutOfRange("day"))?;
-                if let Some(month) = month {
-                    date = date
-                        .with_month(month as u32)
-                        .ok_or(DateParseError::OutOfRange("month"))?;
-                }
-                if let Some(year) = year {
-                    date = date
-                        .with_year(year)
-                        .ok_or(DateParseError::OutOfRange("year"))?;
-                }
+                let month = month.unwrap_or_else(|| local.month() as i32);
+                local = match year {
+                    Some(year) => make_date(year, month, day)?.and_time(local.time()),
+                    None => roll_year(&local, &reference, direction, |year| {
+                        make_date(year, month, day)
+                    })?,
+                };
+            }
+            Some(DateSpec::NthWeekday {
+                nth,
+                weekday,
+                month,
+            }) => {
+                local = match month {
+                    MonthRef::Abs {
+                        month,
+                        year: Some(year),
+                    } => nth_weekday(year, month, weekday, nth)?.and_time(local.time()),
+                    MonthRef::Abs { month, year: None } => {
+                        roll_year(&local, &reference, direction, |year| {
+                            nth_weekday(year, month, weekday, nth)
+                        })?
+                    }
+                    MonthRef::Rel(months) => {
+                        let date = period_start(local.date(), Period::Month, months, week_start)
+                            .ok_or(DateParseError::OutOfRange("month"))?;
+                        nth_weekday(date.year(), date.month() as i32, weekday, nth)?
+                            .and_time(local.time())
+                    }
+                };
+            }
+            Some(DateSpec::IsoWeek {
+                week,
+                weekday,
+                year,
+            }) => {
+                let make = |year| iso_week_date(year, week, weekday);
+                local = match year {
+                    Some(year) => make(year)?.and_time(local.time()),
+                    None => roll_year(&local, &reference, direction, make)?,
+                };
             }
-            Some(DateSpec::Rel { days }) => {
-                date = date.add(Duration::days(days as i64));
+            Some(DateSpec::Ordinal { day, year }) => {
+                let make = |year| ordinal_date(year, day);
+                local = match year {
+                    Some(year) => make(year)?.and_time(local.time()),
+                    None => roll_year(&local, &reference, direction, make)?,
+                };
             }
-            None => {}
+            Some(DateSpec::Boundary {
+                boundary,
+                period,
+                offset,
+            }) => {
+                let date = match boundary {
+                    Boundary::Start => period_start(local.date(), period, offset, week_start),
+                    Boundary::End => offset
+                        .checked_add(1)
+                        .and_then(|x| period_start(local.date(), period, x, week_start))
+                        .and_then(|x| x.pred_opt()),
+                };
+                local = date
+                    .ok_or(DateParseError::OutOfRange("date"))?
+                    .and_time(local.time());
+            }
+            Some(DateSpec::Rel(duration)) => {
+                local = duration
+                    .add_to_local(local)
+                    .ok_or(DateParseError::OutOfRange("relative date"))?;
+            }
+            Some(DateSpec::Weekday { weekday, mode }) => {
+                let current = local.weekday().num_days_from_monday() as i64;
+                let target = weekday.num_days_from_monday() as i64;
+                let days = match mode {
+                    // today is only the right day if the time is on the
+                    // right side of now as well
+                    WeekdayMode::This if direction == TimeDirection::Past => {
+                        let days = -(current - target).rem_euclid(7);
+                        if days == 0 && !direction.accepts(&local, &reference) {
+                            -7
+                        } else {
+                            days
+                        }
+                    }
+                    WeekdayMode::This => {
+                        let days = (target - current).rem_euclid(7);
+                        if days == 0 && !direction.accepts(&local, &reference) {
+                            7
+                        } else {
+                            days
+                        }
+                    }
+                    WeekdayMode::Next => (target - current - 1).rem_euclid(7) + 1,
+                    WeekdayMode::Last => -((current - target - 1).rem_euclid(7) + 1),
+                };
+                local = CalendarDuration::days(days)
+                    .add_to_local(local)
+                    .ok_or(DateParseError::OutOfRange("weekday"))?;
+            }
+            None => {
+                // a bare time moves by a day if it's on the wrong side of now
+                if let Some(TimeSpec::Abs { .. }) = self.time_spec {
+                    if !direction.accepts(&local, &reference) {
+                        let days = if direction == TimeDirection::Past {
+                            -1
+                        } else {
+                            1
+                        };
+                        local = CalendarDuration::days(days)
+                            .add_to_local(local)
+                            .ok_or(DateParseError::OutOfRange("time"))?;
+                    }
+                }
+            }
+        }
+
+        if self.time_spec.is_none() && self.date_spec.is_none() {
+            return Ok((date, None));
+        }
+
+        check_range(&local)?;
+        let (mut rv, adjustment) = self.localize(&date.timezone(), &local)?;
+        if let Some(TimeSpec::Rel(duration)) = self.time_spec {
+            rv = add_exact(rv, &duration)?;
         }
-        Ok(date)
+        Ok((rv, adjustment))
+    }
+}
+
+/// Returns the current time truncated to whole seconds.
+///
+/// Sub-second precision is only shown if the input had it, for the current
+/// time it is just noise.
+fn current_time() -> DateTime<Utc> {
+    let now = Utc::now();
+    now - Duration::nanoseconds(now.nanosecond() as i64)
+}
+
+/// Makes sure a datetime stays clear of the limits of chrono so that it
+/// can be safely converted between timezones.
+fn check_range(dt: &NaiveDateTime) -> Result<(), DateParseError> {
+    if (-200_000..=200_000).contains(&dt.year()) {
+        Ok(())
+    } else {
+        Err(DateParseError::OutOfRange("date"))
     }
 }
 
+fn add_exact(
+    date: DateTime<Zone>,
+    duration: &CalendarDuration,
+) -> Result<DateTime<Zone>, DateParseError> {
+    let rv = duration
+        .exact()
+        .and_then(|x| date.checked_add_signed(x))
+        .ok_or(DateParseError::OutOfRange("relative time"))?;
+    check_range(&rv.naive_utc())?;
+    Ok(rv)
+}
+
 #[derive(Debug)]
 enum TimeSpec {
     Abs {
         hour: i32,
         minute: i32,
         second: i32,
+        nanosecond: u32,
     },
-    Rel {
-        hours: i32,
-        minutes: i32,
-        seconds: i32,
-    },
+    Rel(CalendarDuration),
+    Snap(Rounding),
+}
+
+impl TimeSpec {
+    /// The start of the day.
+    fn midnight() -> TimeSpec {
+        TimeSpec::Abs {
+            hour: 0,
+            minute: 0,
+            second: 0,
+            nanosecond: 0,
+        }
+    }
+
+    fn is_midnight(&self) -> bool {
+        matches!(
+            self,
+            TimeSpec::Abs {
+                hour: 0,
+                minute: 0,
+                second: 0,
+                nanosecond: 0,
+            }
+        )
+    }
 }
 
 #[derive(Debug)]
@@ -313,54 +1190,1143 @@ enum DateSpec {
         month: Option<i32>,
         year: Option<i32>,
     },
-    Rel {
-        days: i32,
+    Rel(CalendarDuration),
+    Weekday {
+        weekday: Weekday,
+        mode: WeekdayMode,
+    },
+    /// The nth weekday of a month, negative values count from the end.
+    NthWeekday {
+        nth: i32,
+        weekday: Weekday,
+        month: MonthRef,
+    },
+    /// A weekday of an ISO 8601 week.
+    IsoWeek {
+        week: u32,
+        weekday: Weekday,
+        year: Option<i32>,
+    },
+    /// A day of the year.
+    Ordinal {
+        day: u32,
+        year: Option<i32>,
+    },
+    /// The first or last day of a period, `offset` periods from now.
+    Boundary {
+        boundary: Boundary,
+        period: Period,
+        offset: i64,
     },
 }
 
-fn as_int(pair: Pair<Rule>) -> i32 {
-    pair.into_inner().next().unwrap().as_str().parse().unwrap()
+/// Refers to a month by name or relative to the current month.
+#[derive(Debug, Clone, Copy)]
+enum MonthRef {
+    Abs { month: i32, year: Option<i32> },
+    Rel(i64),
 }
 
-fn parse_input(expr: &str) -> Result<InputExpr<'_>, DateParseError> {
-    let expr = expr.trim();
-    let pair = DateParser::parse(Rule::spec, expr)
-        .map_err(DateParseError::Parser)?
-        .next()
-        .unwrap();
+/// The start or end of a period.
+#[derive(Debug, Clone, Copy)]
+enum Boundary {
+    Start,
+    End,
+}
 
-    if pair.as_str() != expr {
-        return Err(DateParseError::Garbage(
-            expr[pair.as_str().len()..].to_string(),
-        ));
+/// A calendar period for boundary expressions.
+#[derive(Debug, Clone, Copy)]
+enum Period {
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+/// How a time is rounded to a unit.
+#[derive(Debug, Clone, Copy)]
+enum RoundingMode {
+    Floor,
+    Ceil,
+    Nearest,
+    /// The next multiple strictly after the time ("next full hour").
+    Next,
+}
+
+/// Rounds the wall clock time to a multiple of a unit since midnight.
+#[derive(Debug, Clone, Copy)]
+struct Rounding {
+    mode: RoundingMode,
+    seconds: i64,
+}
+
+impl Rounding {
+    /// Rounds a local time.
+    ///
+    /// This works on the wall clock so that rounding to the hour in a zone
+    /// with a half hour offset ends up on a full local hour.
+    fn apply(&self, local: NaiveDateTime) -> Option<NaiveDateTime> {
+        let midnight = local.date().and_hms_opt(0, 0, 0)?;
+        let elapsed = local.signed_duration_since(midnight).num_nanoseconds()?;
+        let unit = self.seconds * 1_000_000_000;
+        let floor = elapsed - elapsed % unit;
+        let rounded = match self.mode {
+            RoundingMode::Floor => floor,
+            RoundingMode::Ceil if floor == elapsed => floor,
+            RoundingMode::Nearest if (elapsed - floor) * 2 < unit => floor,
+            RoundingMode::Ceil | RoundingMode::Nearest | RoundingMode::Next => floor + unit,
+        };
+        midnight.checked_add_signed(Duration::nanoseconds(rounded))
     }
+}
 
-    let mut rv = InputExpr {
-        time_spec: None,
-        date_spec: None,
-        locations: vec![],
-    };
-    let mut unix_time = false;
+/// How a weekday is resolved relative to the current day.
+///
+/// `This` (also used for a bare weekday) is the upcoming occurrence
+/// including today, `Next` the first one after today and `Last` the
+/// most recent one before today.
+#[derive(Debug, Clone, Copy)]
+enum WeekdayMode {
+    This,
+    Next,
+    Last,
+}
 
-    for piece in pair.into_inner() {
-        match piece.as_rule() {
-            Rule::location => {
-                for loc in piece.as_str().split("->") {
-                    let loc = loc.trim();
-                    if !loc.is_empty() {
-                        rv.locations.push(loc);
-                    }
-                }
-            }
-            Rule::unix_time => {
-                let ts: i64 = piece.into_inner().next().unwrap().as_str().parse().unwrap();
-                let dt = NaiveDateTime::from_timestamp_opt(ts, 0)
-                    .ok_or(DateParseError::OutOfRange("unix timestamp"))?;
-                rv.time_spec = Some(TimeSpec::Abs {
-                    hour: dt.hour() as _,
-                    minute: dt.minute() as _,
-                    second: dt.second() as _,
-                });
+const WEEKDAY_NAMES: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+const ORDINALS: [&str; 5] = ["first", "second", "third", "fourth", "fifth"];
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Checks that a timezone abbreviation is in effect at the given time.
+///
+/// This rejects `PDT` for a date in the winter for instance.
+fn check_abbreviation(abbrev: Option<&str>, dt: &DateTime<Zone>) -> Result<(), DateParseError> {
+    if let Some(abbrev) = abbrev {
+        let actual = dt.offset().to_string();
+        if !actual.eq_ignore_ascii_case(abbrev) {
+            return Err(DateParseError::InactiveAbbreviation(
+                abbrev.to_string(),
+                format!(
+                    "{} observes {} on {}",
+                    dt.timezone().name(),
+                    actual,
+                    dt.format("%Y-%m-%d")
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Creates a date from an ISO 8601 year, week and weekday.
+fn iso_week_date(year: i32, week: u32, weekday: Weekday) -> Result<NaiveDate, DateParseError> {
+    if week == 0 {
+        return Err(DateParseError::InvalidDate("there is no week 0".into()));
+    }
+    NaiveDate::from_isoywd_opt(year, week, weekday).ok_or_else(|| {
+        if (-200_000..=200_000).contains(&year) {
+            DateParseError::InvalidDate(format!("{} has no week {}", year, week))
+        } else {
+            DateParseError::OutOfRange("year")
+        }
+    })
+}
+
+/// Creates a date from a year and a day of the year.
+fn ordinal_date(year: i32, day: u32) -> Result<NaiveDate, DateParseError> {
+    if day == 0 {
+        return Err(DateParseError::InvalidDate("there is no day 0".into()));
+    }
+    NaiveDate::from_yo_opt(year, day).ok_or_else(|| match NaiveDate::from_ymd_opt(year, 12, 31) {
+        Some(last) => {
+            DateParseError::InvalidDate(format!("{} has only {} days", year, last.ordinal()))
+        }
+        None => DateParseError::OutOfRange("year"),
+    })
+}
+
+/// Validates the components and creates a date from it.
+fn make_date(year: i32, month: i32, day: i32) -> Result<NaiveDate, DateParseError> {
+    if !(1..=12).contains(&month) {
+        return Err(DateParseError::InvalidDate(format!(
+            "there is no month {}",
+            month
+        )));
+    }
+    if day < 1 {
+        return Err(DateParseError::InvalidDate(format!(
+            "there is no day {}",
+            day
+        )));
+    }
+    let days = days_in_month(year, month as u32);
+    if day as u32 > days {
+        let month_name = MONTH_NAMES[month as usize - 1];
+        return Err(DateParseError::InvalidDate(if month == 2 {
+            format!("{} {} has only {} days", month_name, year, days)
+        } else {
+            format!("{} has only {} days", month_name, days)
+        }));
+    }
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+        .ok_or(DateParseError::OutOfRange("year"))
+}
+
+/// Returns the nth weekday of a month, a negative `nth` counts from the end.
+fn nth_weekday(
+    year: i32,
+    month: i32,
+    weekday: Weekday,
+    nth: i32,
+) -> Result<NaiveDate, DateParseError> {
+    let first = make_date(year, month, 1)?;
+    let last = make_date(year, month, days_in_month(year, month as u32) as i32)?;
+    let target = weekday.num_days_from_monday() as i64;
+    let date = if nth > 0 {
+        let days = (target - first.weekday().num_days_from_monday() as i64).rem_euclid(7);
+        first.checked_add_signed(Duration::days(days + 7 * (nth as i64 - 1)))
+    } else {
+        let days = (last.weekday().num_days_from_monday() as i64 - target).rem_euclid(7);
+        last.checked_sub_signed(Duration::days(days + 7 * (-nth as i64 - 1)))
+    };
+    match date {
+        Some(date) if date.month() == first.month() => Ok(date),
+        _ => Err(DateParseError::InvalidDate(format!(
+            "{} {} has no {} {}",
+            MONTH_NAMES[month as usize - 1],
+            year,
+            ORDINALS
+                .get(nth.unsigned_abs() as usize - 1)
+                .unwrap_or(&"such"),
+            WEEKDAY_NAMES[target as usize],
+        ))),
+    }
+}
+
+/// Returns the first day of the period that is `offset` periods away from
+/// the period containing `date`.
+fn period_start(
+    date: NaiveDate,
+    period: Period,
+    offset: i64,
+    week_start: WeekStart,
+) -> Option<NaiveDate> {
+    let (start, step) = match period {
+        Period::Day => (date, CalendarDuration::days(1)),
+        Period::Week => {
+            let days = (date.weekday().num_days_from_monday() + 7
+                - week_start.weekday().num_days_from_monday())
+                % 7;
+            (
+                date.checked_sub_signed(Duration::days(days as i64))?,
+                CalendarDuration::days(7),
+            )
+        }
+        Period::Month => (date.with_day(1)?, CalendarDuration::months(1)),
+        Period::Quarter => (
+            NaiveDate::from_ymd_opt(date.year(), date.month0() / 3 * 3 + 1, 1)?,
+            CalendarDuration::months(3),
+        ),
+        Period::Year => (
+            NaiveDate::from_ymd_opt(date.year(), 1, 1)?,
+            CalendarDuration::months(12),
+        ),
+    };
+    let shift = CalendarDuration {
+        months: step.months.checked_mul(offset)?,
+        days: step.days.checked_mul(offset)?,
+        seconds: 0,
+    };
+    Some(shift.add_to_local(start.and_hms(0, 0, 0))?.date())
+}
+
+/// Picks the year for a year-less date according to the direction.
+///
+/// The date is created for a year by `make`.  The search starts at the
+/// current year and goes up to eight years forward or backward so that
+/// February 29th finds the closest leap year.
+fn roll_year<F>(
+    local: &NaiveDateTime,
+    now: &NaiveDateTime,
+    direction: TimeDirection,
+    make: F,
+) -> Result<NaiveDateTime, DateParseError>
+where
+    F: Fn(i32) -> Result<NaiveDate, DateParseError>,
+{
+    let step = match direction {
+        TimeDirection::Current => return Ok(make(local.year())?.and_time(local.time())),
+        TimeDirection::Future => 1,
+        TimeDirection::Past => -1,
+    };
+    let mut first_err = None;
+    for offset in 0..=8 {
+        match make(local.year() + step * offset) {
+            Ok(date) => {
+                let rv = date.and_time(local.time());
+                if direction.accepts(&rv, now) {
+                    return Ok(rv);
+                }
+            }
+            Err(err) => {
+                first_err.get_or_insert(err);
+            }
+        }
+    }
+    Err(first_err.unwrap_or(DateParseError::OutOfRange("year")))
+}
+
+fn parse_rel_duration(pair: Pair<Rule>) -> Result<CalendarDuration, DateParseError> {
+    let mut rv = CalendarDuration::default();
+    for piece in pair.into_inner() {
+        let (factor, unit) = match piece.as_rule() {
+            Rule::iso_duration => {
+                rv = parse_rel_duration(piece)?;
+                continue;
+            }
+            Rule::rel_years | Rule::iso_years => (12, CalendarDuration::months(1)),
+            Rule::rel_months | Rule::iso_months => (1, CalendarDuration::months(1)),
+            Rule::rel_weeks | Rule::iso_weeks => (7, CalendarDuration::days(1)),
+            Rule::rel_days | Rule::iso_days => (1, CalendarDuration::days(1)),
+            Rule::rel_hours | Rule::iso_hours => (3600, CalendarDuration::seconds(1)),
+            Rule::rel_minutes | Rule::iso_minutes => (60, CalendarDuration::seconds(1)),
+            Rule::rel_seconds | Rule::iso_seconds => (1, CalendarDuration::seconds(1)),
+            _ => unreachable!(),
+        };
+        let (numerator, denominator) = parse_quantity(piece)?;
+        let total = numerator
+            .checked_mul(factor)
+            .ok_or(DateParseError::OutOfRange("relative time"))?;
+        let (whole, rest) = (total / denominator, total % denominator);
+        let mut value = CalendarDuration {
+            months: unit.months * whole,
+            days: unit.days * whole,
+            seconds: unit.seconds * whole,
+        };
+        // fractional days carry over into seconds, fractional seconds are
+        // dropped.  Months have no fixed length so they have to add up.
+        if rest != 0 && unit.months != 0 {
+            return Err(DateParseError::InvalidDate(
+                "fractional months are not supported".into(),
+            ));
+        } else if unit.days != 0 {
+            value.seconds = rest * 86400 / denominator;
+        }
+        rv = rv
+            .checked_add(value)
+            .ok_or(DateParseError::OutOfRange("relative time"))?;
+    }
+    Ok(rv)
+}
+
+/// Parses the quantity of a relative time unit as fraction.
+///
+/// This handles numbers, decimals (`1.5`), number words (`twenty-five`),
+/// articles (`an hour`) and halves or quarters (`half an hour`, `three
+/// quarters of an hour`, `an hour and a half`).
+fn parse_quantity(pair: Pair<Rule>) -> Result<(i64, i64), DateParseError> {
+    let mut rv = (1, 1);
+    for piece in pair.into_inner() {
+        rv = match piece.as_rule() {
+            Rule::number => (as_number(piece.as_str(), "relative time")?, 1),
+            Rule::decimal => {
+                let (whole, fraction) = piece.as_str().split_once('.').unwrap();
+                let denominator = 10i64.pow(fraction.len() as u32);
+                let numerator = as_number(whole, "relative time")?
+                    .checked_mul(denominator)
+                    .and_then(|x| x.checked_add(fraction.parse().ok()?))
+                    .ok_or(DateParseError::OutOfRange("relative time"))?;
+                (numerator, denominator)
+            }
+            Rule::number_word => (parse_number_word(piece.as_str()), 1),
+            Rule::article => (1, 1),
+            Rule::half_of => (1, 2),
+            Rule::quarters_of => match piece.into_inner().next().unwrap() {
+                count if count.as_rule() == Rule::number_word => {
+                    (parse_number_word(count.as_str()), 4)
+                }
+                _ => (1, 4),
+            },
+            Rule::and_a_half => {
+                let (numerator, denominator) = rv;
+                (
+                    numerator
+                        .checked_mul(2)
+                        .and_then(|x| x.checked_add(denominator))
+                        .ok_or(DateParseError::OutOfRange("relative time"))?,
+                    denominator * 2,
+                )
+            }
+            _ => unreachable!(),
+        };
+    }
+    Ok(rv)
+}
+
+fn as_number(value: &str, context: &'static str) -> Result<i64, DateParseError> {
+    value
+        .parse()
+        .map_err(|_| DateParseError::OutOfRange(context))
+}
+
+const NUMBER_WORDS: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS_WORDS: [&str; 8] = [
+    "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// Parses an English number word (`seven`, `twenty-five`).
+fn parse_number_word(value: &str) -> i64 {
+    value
+        .split(|c: char| c == '-' || c.is_whitespace())
+        .filter(|x| !x.is_empty())
+        .map(|word| {
+            let word = word.to_ascii_lowercase();
+            if let Some(idx) = NUMBER_WORDS.iter().position(|x| *x == word) {
+                idx as i64
+            } else {
+                let idx = TENS_WORDS.iter().position(|x| *x == word).unwrap();
+                (idx as i64 + 2) * 10
+            }
+        })
+        .sum()
+}
+
+/// Parses a time like `quarter past 3`, `half past ten` or `10 minutes to 5pm`.
+fn parse_time_past(pair: Pair<Rule>) -> Result<(i32, i32), DateParseError> {
+    let mut minutes = 0;
+    let mut hour = 0;
+    let mut to = false;
+    for piece in pair.into_inner() {
+        match piece.as_rule() {
+            Rule::quarter_hour_word => minutes = 15,
+            Rule::half_hour_word => minutes = 30,
+            Rule::past_minutes | Rule::to_minutes => {
+                minutes = parse_count(piece.into_inner().next().unwrap())?;
+            }
+            Rule::minutes_to => to = true,
+            Rule::past_hour => {
+                let mut pieces = piece.into_inner();
+                hour = parse_count(pieces.next().unwrap())?;
+                if let Some(meridiem) = pieces.next() {
+                    if !(1..=12).contains(&hour) {
+                        return Err(DateParseError::OutOfRange("hour"));
+                    }
+                    hour = apply_meridiem(hour, meridiem);
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+    if hour > 23 {
+        return Err(DateParseError::OutOfRange("hour"));
+    } else if minutes > 59 {
+        return Err(DateParseError::OutOfRange("minute"));
+    }
+    let rv = if to {
+        (hour * 60 - minutes).rem_euclid(24 * 60)
+    } else {
+        hour * 60 + minutes
+    };
+    Ok((rv / 60, rv % 60))
+}
+
+/// Parses a small count given as number or number word.
+fn parse_count(pair: Pair<Rule>) -> Result<i32, DateParseError> {
+    match pair.as_rule() {
+        Rule::number_word => Ok(parse_number_word(pair.as_str()) as i32),
+        _ => pair
+            .as_str()
+            .parse()
+            .map_err(|_| DateParseError::OutOfRange("time")),
+    }
+}
+
+/// Converts a 12-hour clock hour to 24 hours.
+fn apply_meridiem(hour: i32, meridiem: Pair<Rule>) -> i32 {
+    if matches!(meridiem.into_inner().next().unwrap().as_rule(), Rule::pm) {
+        // don't change for 12pm
+        if hour != 12 {
+            return hour + 12;
+        }
+    } else if hour == 12 {
+        // special case 12am = midnight
+        return 0;
+    }
+    hour
+}
+
+fn parse_iso_date(pair: Pair<Rule>) -> (i32, i32, i32) {
+    let mut pieces = pair.into_inner();
+    let year = pieces.next().unwrap().as_str().parse().unwrap();
+    let month = pieces.next().unwrap().as_str().parse().unwrap();
+    let day = pieces.next().unwrap().as_str().parse().unwrap();
+    (year, month, day)
+}
+
+fn parse_iso_time(pair: Pair<Rule>) -> TimeSpec {
+    let mut hour = 0;
+    let mut minute = 0;
+    let mut second = 0;
+    let mut nanosecond = 0;
+    for time_piece in pair.into_inner() {
+        match time_piece.as_rule() {
+            Rule::HH24 => {
+                hour = time_piece.as_str().parse::<i32>().unwrap();
+            }
+            Rule::MM => {
+                minute = time_piece.as_str().parse::<i32>().unwrap();
+            }
+            Rule::SS => {
+                second = time_piece.as_str().parse::<i32>().unwrap();
+            }
+            Rule::iso_fraction => {
+                nanosecond = parse_fraction(&time_piece.as_str()[1..]);
+            }
+            _ => unreachable!(),
+        }
+    }
+    TimeSpec::Abs {
+        hour,
+        minute,
+        second,
+        nanosecond,
+    }
+}
+
+fn parse_numeric_date(
+    pair: Pair<Rule>,
+    date_order: Option<DateOrder>,
+) -> Result<(i32, i32, Option<i32>), DateParseError> {
+    let input = pair.as_str();
+    let mut parts = vec![];
+    let mut separator = "";
+    let mut year = None;
+    let mut year_first = false;
+    for date_piece in pair.into_inner() {
+        match date_piece.as_rule() {
+            Rule::yyyy => {
+                year_first = parts.is_empty();
+                year = Some(date_piece.as_str().parse().unwrap());
+            }
+            Rule::date_sep => {
+                if separator.is_empty() {
+                    separator = date_piece.as_str();
+                }
+            }
+            Rule::date_part => {
+                parts.push(date_piece.as_str().parse::<i32>().unwrap());
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    let (first, second) = (parts[0], parts[1]);
+    let day_first = if year_first {
+        false
+    } else {
+        match date_order {
+            Some(DateOrder::Dmy) => true,
+            Some(DateOrder::Mdy) => false,
+            _ if separator == "." => true,
+            // year, month and day without the year is month and day
+            Some(DateOrder::Ymd) => false,
+            None => {
+                if first == second || (first > 12 && second <= 12) {
+                    true
+                } else if second > 12 && first <= 12 {
+                    false
+                } else {
+                    return Err(DateParseError::AmbiguousDate(input.to_string()));
+                }
+            }
+        }
+    };
+
+    Ok(if day_first {
+        (first, second, year)
+    } else {
+        (second, first, year)
+    })
+}
+
+fn parse_english_month(pair: Pair<Rule>) -> i32 {
+    match pair.into_inner().next().unwrap().as_rule() {
+        Rule::m01 => 1,
+        Rule::m02 => 2,
+        Rule::m03 => 3,
+        Rule::m04 => 4,
+        Rule::m05 => 5,
+        Rule::m06 => 6,
+        Rule::m07 => 7,
+        Rule::m08 => 8,
+        Rule::m09 => 9,
+        Rule::m10 => 10,
+        Rule::m11 => 11,
+        Rule::m12 => 12,
+        _ => unreachable!(),
+    }
+}
+
+fn parse_weekday(pair: Pair<Rule>) -> Weekday {
+    match pair.into_inner().next().unwrap().as_rule() {
+        Rule::mon => Weekday::Mon,
+        Rule::tue => Weekday::Tue,
+        Rule::wed => Weekday::Wed,
+        Rule::thu => Weekday::Thu,
+        Rule::fri => Weekday::Fri,
+        Rule::sat => Weekday::Sat,
+        Rule::sun => Weekday::Sun,
+        _ => unreachable!(),
+    }
+}
+
+/// Parses a time and the zone attached to it.
+///
+/// `now` has no time spec as it refers to the current time.
+fn parse_time<'a>(
+    pair: Pair<'_, Rule>,
+    source: Source<'a>,
+    options: &ParseOptions,
+) -> Result<(Option<TimeSpec>, Option<&'a str>), DateParseError> {
+    let mut hour = 0;
+    let mut minute = 0;
+    let mut second = 0;
+    let mut zone = None;
+    let mut now = false;
+    for piece in pair.into_inner() {
+        match piece.as_rule() {
+            Rule::HH12 | Rule::HH24 => {
+                hour = piece.as_str().parse::<i32>().unwrap();
+            }
+            Rule::MM => {
+                minute = piece.as_str().parse::<i32>().unwrap();
+            }
+            Rule::SS => {
+                second = piece.as_str().parse::<i32>().unwrap();
+            }
+            Rule::meridiem => {
+                hour = apply_meridiem(hour, piece);
+            }
+            Rule::time_past => {
+                let (past_hour, past_minute) = parse_time_past(piece)?;
+                hour = past_hour;
+                minute = past_minute;
+            }
+            Rule::time_zone | Rule::military_time_zone => {
+                zone = Some(military_zone(piece.as_str()).unwrap_or_else(|| source.text(&piece)));
+            }
+            Rule::time_special => {
+                if piece.as_str().eq_ignore_ascii_case("midnight") {
+                    hour = 0;
+                } else if piece.as_str().eq_ignore_ascii_case("noon") {
+                    hour = 12;
+                } else if piece.as_str().eq_ignore_ascii_case("now") {
+                    now = true;
+                }
+            }
+            Rule::day_period => {
+                let time = parse_day_period(piece, options)?;
+                hour = time.hour() as i32;
+                minute = time.minute() as i32;
+                second = time.second() as i32;
+            }
+            _ => unreachable!(),
+        }
+    }
+    if now {
+        return Ok((None, zone));
+    }
+    let time_spec = TimeSpec::Abs {
+        hour,
+        minute,
+        second,
+        nanosecond: 0,
+    };
+    Ok((Some(time_spec), zone))
+}
+
+/// Resolves a time-of-day word (`morning`, `tonight`) to its time.
+fn parse_day_period(
+    pair: Pair<'_, Rule>,
+    options: &ParseOptions,
+) -> Result<NaiveTime, DateParseError> {
+    let period = match pair.into_inner().next().unwrap().as_rule() {
+        Rule::early_morning => TimeOfDay::EarlyMorning,
+        Rule::morning => TimeOfDay::Morning,
+        Rule::late_morning => TimeOfDay::LateMorning,
+        Rule::lunchtime => TimeOfDay::Noon,
+        Rule::afternoon => TimeOfDay::Afternoon,
+        Rule::early_evening | Rule::close_of_business => TimeOfDay::EarlyEvening,
+        Rule::evening | Rule::tonight => TimeOfDay::Evening,
+        Rule::late_evening => TimeOfDay::LateEvening,
+        Rule::night => TimeOfDay::Night,
+        _ => unreachable!(),
+    };
+    let time = options
+        .times_of_day
+        .get(&period)
+        .copied()
+        .unwrap_or_else(|| period.default_time());
+    // the time has to be described the same way in the output
+    if TimeOfDay::from_hour(time.hour()) != period {
+        return Err(DateParseError::InvalidDate(format!(
+            "{} does not fall into {}",
+            time.format("%H:%M"),
+            period
+        )));
+    }
+    Ok(time)
+}
+
+/// Parses a period qualifier into an offset (`next` is 1, `last` is -1).
+fn parse_period_qualifier(pair: Pair<Rule>) -> i64 {
+    match pair.into_inner().next().unwrap().as_rule() {
+        Rule::next => 1,
+        Rule::last => -1,
+        Rule::this => 0,
+        _ => unreachable!(),
+    }
+}
+
+/// Parses `2026-W42-3` or `monday of week 42`, the weekday defaults to monday.
+fn parse_week_date(pair: Pair<Rule>) -> DateSpec {
+    let mut week = 1;
+    let mut weekday = Weekday::Mon;
+    let mut year = None;
+    for piece in pair.into_inner() {
+        match piece.as_rule() {
+            Rule::yyyy => year = Some(piece.as_str().parse().unwrap()),
+            Rule::week_number => week = piece.as_str().parse().unwrap(),
+            Rule::iso_weekday => {
+                weekday = WEEKDAYS[piece.as_str().parse::<usize>().unwrap() - 1];
+            }
+            Rule::weekday => weekday = parse_weekday(piece),
+            _ => unreachable!(),
+        }
+    }
+    DateSpec::IsoWeek {
+        week,
+        weekday,
+        year,
+    }
+}
+
+/// Parses `2026-289` or `day 289 of 2026`.
+fn parse_ordinal_date(pair: Pair<Rule>) -> DateSpec {
+    let mut day = 1;
+    let mut year = None;
+    for piece in pair.into_inner() {
+        match piece.as_rule() {
+            Rule::yyyy => year = Some(piece.as_str().parse().unwrap()),
+            Rule::day_of_year => day = piece.as_str().parse().unwrap(),
+            _ => unreachable!(),
+        }
+    }
+    DateSpec::Ordinal { day, year }
+}
+
+fn parse_nth_weekday(pair: Pair<Rule>) -> DateSpec {
+    let mut nth = 1;
+    let mut weekday = Weekday::Mon;
+    let mut month = MonthRef::Rel(0);
+    for piece in pair.into_inner() {
+        match piece.as_rule() {
+            Rule::ordinal => {
+                nth = match &piece.as_str().to_ascii_lowercase()[..] {
+                    "first" | "1st" => 1,
+                    "second" | "2nd" => 2,
+                    "third" | "3rd" => 3,
+                    "fourth" | "4th" => 4,
+                    "fifth" | "5th" => 5,
+                    "last" => -1,
+                    _ => unreachable!(),
+                };
+            }
+            Rule::weekday => {
+                weekday = parse_weekday(piece);
+            }
+            Rule::month_ref => {
+                let mut year = None;
+                for month_piece in piece.into_inner() {
+                    match month_piece.as_rule() {
+                        Rule::english_month => {
+                            month = MonthRef::Abs {
+                                month: parse_english_month(month_piece),
+                                year: None,
+                            };
+                        }
+                        Rule::yyyy => {
+                            year = Some(month_piece.as_str().parse().unwrap());
+                        }
+                        Rule::period_qualifier => {
+                            month = MonthRef::Rel(parse_period_qualifier(month_piece));
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+                if let MonthRef::Abs {
+                    month: month_num, ..
+                } = month
+                {
+                    month = MonthRef::Abs {
+                        month: month_num,
+                        year,
+                    };
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+    DateSpec::NthWeekday {
+        nth,
+        weekday,
+        month,
+    }
+}
+
+fn parse_boundary(pair: Pair<Rule>) -> DateSpec {
+    let mut boundary = Boundary::Start;
+    let mut period = Period::Day;
+    let mut offset = 0;
+    for piece in pair.into_inner() {
+        match piece.as_rule() {
+            Rule::boundary => {
+                boundary = match piece.into_inner().next().unwrap().as_rule() {
+                    Rule::start => Boundary::Start,
+                    Rule::end => Boundary::End,
+                    _ => unreachable!(),
+                };
+            }
+            Rule::period_qualifier => {
+                offset = parse_period_qualifier(piece);
+            }
+            Rule::period => {
+                period = match piece.into_inner().next().unwrap().as_rule() {
+                    Rule::day => Period::Day,
+                    Rule::week => Period::Week,
+                    Rule::month => Period::Month,
+                    Rule::quarter => Period::Quarter,
+                    Rule::year => Period::Year,
+                    _ => unreachable!(),
+                };
+            }
+            _ => unreachable!(),
+        }
+    }
+    DateSpec::Boundary {
+        boundary,
+        period,
+        offset,
+    }
+}
+
+/// Parses a rounding suffix (`rounded down to 15 minutes`).
+fn parse_rounding(pair: Pair<Rule>) -> Result<Rounding, DateParseError> {
+    let mut mode = RoundingMode::Nearest;
+    let mut count: i64 = 1;
+    let mut unit = 1;
+    for piece in pair.into_inner() {
+        match piece.as_rule() {
+            Rule::round_up => mode = RoundingMode::Ceil,
+            Rule::round_down => mode = RoundingMode::Floor,
+            Rule::number => {
+                count = piece
+                    .as_str()
+                    .parse()
+                    .map_err(|_| DateParseError::OutOfRange("rounding"))?;
+            }
+            _ => unit = rounding_unit(piece.as_rule()),
+        }
+    }
+    match count.checked_mul(unit) {
+        Some(seconds @ 1..=86400) => Ok(Rounding { mode, seconds }),
+        _ => Err(DateParseError::OutOfRange("rounding")),
+    }
+}
+
+/// Parses a rounded time (`top of the hour`, `next half hour`).
+fn parse_snap(pair: Pair<Rule>) -> Rounding {
+    let piece = pair.into_inner().next().unwrap();
+    match piece.as_rule() {
+        Rule::top_of_hour => Rounding {
+            mode: RoundingMode::Ceil,
+            seconds: 3600,
+        },
+        rule => Rounding {
+            mode: RoundingMode::Next,
+            seconds: rounding_unit(rule),
+        },
+    }
+}
+
+/// Returns the length of a rounding unit in seconds.
+fn rounding_unit(rule: Rule) -> i64 {
+    match rule {
+        Rule::round_days => 86400,
+        Rule::round_hours => 3600,
+        Rule::half_hour => 1800,
+        Rule::quarter_hour => 900,
+        Rule::round_minutes => 60,
+        Rule::round_seconds => 1,
+        _ => unreachable!(),
+    }
+}
+
+/// Maps the zone of an RFC 2822 style timestamp to an offset.
+///
+/// The named zones are the ones RFC 2822 defines with fixed offsets.
+fn rfc_zone_offset(zone: &str) -> &str {
+    match zone.to_ascii_uppercase().as_str() {
+        "GMT" | "UTC" | "UT" => "Z",
+        "EST" => "-05:00",
+        "EDT" => "-04:00",
+        "CST" => "-06:00",
+        "CDT" => "-05:00",
+        "MST" => "-07:00",
+        "MDT" => "-06:00",
+        "PST" => "-08:00",
+        "PDT" => "-07:00",
+        _ => zone,
+    }
+}
+
+/// Parses a unix timestamp in seconds, milliseconds, microseconds or nanoseconds.
+///
+/// Without an explicit unit the unit is picked from the number of integer
+/// digits: up to 12 digits are seconds, 13 and more milliseconds, 16 and
+/// more microseconds and 19 and more nanoseconds.  Fractions below a
+/// nanosecond are truncated.
+fn parse_unix_time(pair: Pair<'_, Rule>) -> Result<NaiveDateTime, DateParseError> {
+    let mut unit = None;
+    let mut value = "";
+    for piece in pair.into_inner() {
+        match piece.as_rule() {
+            Rule::unix_unit => unit = Some(piece.as_str().to_ascii_lowercase()),
+            Rule::unix_value => value = piece.as_str(),
+            _ => unreachable!(),
+        }
+    }
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let (int, fraction) = value.split_once('.').unwrap_or((value, ""));
+    let digits = match unit.as_deref() {
+        Some("s") => 0,
+        Some("ms") => 3,
+        Some("us") => 6,
+        Some("ns") => 9,
+        // other lengths are seconds and larger ones are beyond any date
+        _ => match int.trim_start_matches('0').len() {
+            13 => 3,
+            16 => 6,
+            19 => 9,
+            20.. => return Err(DateParseError::OutOfRange("unix timestamp")),
+            _ => 0,
+        },
+    };
+
+    // scale the value to nanoseconds: the whole units and as many digits of
+    // the fraction as are needed to reach nanosecond precision
+    let fraction = &fraction[..fraction.len().min(9 - digits)];
+    let nanos = int
+        .parse::<i128>()
+        .ok()
+        .and_then(|x| x.checked_mul(10i128.pow(9 - digits as u32)))
+        .and_then(|x| {
+            let scale = 10i128.pow((9 - digits - fraction.len()) as u32);
+            x.checked_add(fraction.parse::<i128>().unwrap_or(0) * scale)
+        })
+        .ok_or(DateParseError::OutOfRange("unix timestamp"))?;
+    let nanos = if negative { -nanos } else { nanos };
+    i64::try_from(nanos.div_euclid(1_000_000_000))
+        .ok()
+        .and_then(|secs| {
+            NaiveDateTime::from_timestamp_opt(secs, nanos.rem_euclid(1_000_000_000) as u32)
+        })
+        .ok_or(DateParseError::OutOfRange("unix timestamp"))
+}
+
+fn parse_fraction(digits: &str) -> u32 {
+    // only nanosecond precision is retained
+    let digits = &digits[..digits.len().min(9)];
+    digits.parse::<u32>().unwrap() * 10u32.pow(9 - digits.len() as u32)
+}
+
+fn parse_timestamp_offset(offset: &str) -> Result<ZoneRef, DateParseError> {
+    if offset.eq_ignore_ascii_case("z") {
+        Ok(ZoneRef::Tz(Tz::UTC))
+    } else {
+        parse_offset(offset)
+            .map(ZoneRef::Offset)
+            .ok_or(DateParseError::OutOfRange("utc offset"))
+    }
+}
+
+/// The original text of an expression that is parsed in translation.
+///
+/// Translations keep the byte offsets of the expression, so the span of a
+/// piece in the parsed text refers to the same text in the original.
+#[derive(Copy, Clone)]
+struct Source<'a> {
+    expr: &'a str,
+    offset: usize,
+}
+
+impl<'a> Source<'a> {
+    /// Returns the original text of a parsed piece.
+    fn text(&self, pair: &Pair<'_, Rule>) -> &'a str {
+        let span = pair.as_span();
+        let mut start = self.offset + span.start();
+        let mut end = self.offset + span.end();
+        while !self.expr.is_char_boundary(start) {
+            start -= 1;
+        }
+        while !self.expr.is_char_boundary(end) {
+            end += 1;
+        }
+        &self.expr[start..end]
+    }
+}
+
+fn parse_input<'a>(expr: &'a str, options: &ParseOptions) -> Result<InputExpr<'a>, DateParseError> {
+    let rv = parse_input_translated(expr, options, true);
+    // some English keywords are words of the language as well (`en` is
+    // French for `in`) so an expression that fails is retried as English
+    if rv.is_err() && options.language != Language::English {
+        if let Ok(rv) = parse_input_translated(expr, options, false) {
+            return Ok(rv);
+        }
+    }
+    rv
+}
+
+fn parse_input_translated<'a>(
+    expr: &'a str,
+    options: &ParseOptions,
+    translate: bool,
+) -> Result<InputExpr<'a>, DateParseError> {
+    let expr = expr.trim();
+    let translated = if translate {
+        options.language.translate(expr)
+    } else {
+        None
+    };
+    let text = translated.as_deref().unwrap_or(expr);
+    // removed words leave padding which can end up at the edges
+    let trimmed = text.trim_start();
+    let source = Source {
+        expr,
+        offset: text.len() - trimmed.len(),
+    };
+    let text = trimmed.trim_end();
+    let pair = DateParser::parse(Rule::spec, text)
+        .map_err(|err| DateParseError::Parser(Box::new(err)))?
+        .next()
+        .unwrap();
+
+    if pair.as_str() != text {
+        let leftover = source.offset + pair.as_str().len();
+        return Err(DateParseError::Garbage(
+            expr.get(leftover..)
+                .unwrap_or(&text[pair.as_str().len()..])
+                .to_string(),
+        ));
+    }
+
+    let mut rv = InputExpr {
+        time_spec: None,
+        date_spec: None,
+        end_time: None,
+        offset: CalendarDuration::default(),
+        rounding: None,
+        locations: vec![],
+        source_zone: None,
+        zone_suffix: None,
+        options: options.clone(),
+    };
+    let mut unix_time = false;
+    let mut source_offset = None;
+    let mut zone_suffix = None;
+    let mut arrow = false;
+
+    for piece in pair.into_inner() {
+        match piece.as_rule() {
+            Rule::arrow => {
+                arrow = true;
+            }
+            Rule::location => {
+                for loc in source.text(&piece).split("->") {
+                    let loc = loc.trim();
+                    if !loc.is_empty() {
+                        rv.locations
+                            .push(options.language.place(loc).unwrap_or(loc));
+                    }
+                }
+            }
+            Rule::unix_time => {
+                let dt = parse_unix_time(piece)?;
+                rv.time_spec = Some(TimeSpec::Abs {
+                    hour: dt.hour() as _,
+                    minute: dt.minute() as _,
+                    second: dt.second() as _,
+                    nanosecond: dt.nanosecond(),
+                });
                 rv.date_spec = Some(DateSpec::Abs {
                     day: dt.day() as _,
                     month: Some(dt.month() as _),
@@ -368,60 +2334,131 @@ fn parse_input(expr: &str) -> Result<InputExpr<'_>, DateParseError> {
                 });
                 unix_time = true;
             }
+            Rule::iso_datetime => {
+                // a date without a time is the whole day
+                rv.time_spec = Some(TimeSpec::midnight());
+                rv.end_time = Some(TimeSpec::midnight());
+                for iso_piece in piece.into_inner() {
+                    match iso_piece.as_rule() {
+                        Rule::iso_date => {
+                            let (year, month, day) = parse_iso_date(iso_piece);
+                            rv.date_spec = Some(DateSpec::Abs {
+                                day,
+                                month: Some(month),
+                                year: Some(year),
+                            });
+                        }
+                        Rule::iso_time => {
+                            rv.time_spec = Some(parse_iso_time(iso_piece));
+                            rv.end_time = None;
+                        }
+                        Rule::iso_offset => {
+                            source_offset = Some(source.text(&iso_piece));
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+            }
+            Rule::rfc2822_datetime
+            | Rule::rfc850_datetime
+            | Rule::asctime_datetime
+            | Rule::syslog_datetime => {
+                let mut day = 0;
+                let mut month = None;
+                let mut year = None;
+                let mut hour = 0;
+                let mut minute = 0;
+                let mut second = 0;
+                let mut weekday = None;
+                for wire_piece in piece.into_inner() {
+                    match wire_piece.as_rule() {
+                        Rule::weekday => {
+                            weekday = Some(parse_weekday(wire_piece));
+                        }
+                        Rule::rfc_day => {
+                            day = wire_piece.as_str().parse().unwrap();
+                        }
+                        Rule::english_month => {
+                            month = Some(parse_english_month(wire_piece));
+                        }
+                        Rule::yyyy => {
+                            year = Some(wire_piece.as_str().parse().unwrap());
+                        }
+                        Rule::yy => {
+                            // two digit years as interpreted by RFC 2822
+                            let yy: i32 = wire_piece.as_str().parse().unwrap();
+                            year = Some(if yy < 50 { 2000 + yy } else { 1900 + yy });
+                        }
+                        Rule::iso_time => {
+                            rv.time_spec = Some(parse_iso_time(wire_piece));
+                        }
+                        Rule::HH24 => {
+                            hour = wire_piece.as_str().parse().unwrap();
+                        }
+                        Rule::MM => {
+                            minute = wire_piece.as_str().parse().unwrap();
+                        }
+                        Rule::SS => {
+                            second = wire_piece.as_str().parse().unwrap();
+                        }
+                        Rule::rfc_zone => {
+                            source_offset = Some(rfc_zone_offset(source.text(&wire_piece)));
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+                if rv.time_spec.is_none() {
+                    rv.time_spec = Some(TimeSpec::Abs {
+                        hour,
+                        minute,
+                        second,
+                        nanosecond: 0,
+                    });
+                }
+                // the weekday is redundant with the date but has to agree
+                if let (Some(weekday), Some(month), Some(year)) = (weekday, month, year) {
+                    let date = make_date(year, month, day)?;
+                    if date.weekday() != weekday {
+                        return Err(DateParseError::InvalidDate(format!(
+                            "{} {} {} is a {}, not a {}",
+                            day,
+                            MONTH_NAMES[month as usize - 1],
+                            year,
+                            WEEKDAY_NAMES[date.weekday().num_days_from_monday() as usize],
+                            WEEKDAY_NAMES[weekday.num_days_from_monday() as usize],
+                        )));
+                    }
+                }
+                rv.date_spec = Some(DateSpec::Abs { day, month, year });
+            }
             Rule::abs_time => {
-                let mut now = false;
+                let mut has_time = false;
                 for abs_time_piece in piece.into_inner() {
                     match abs_time_piece.as_rule() {
                         Rule::time => {
-                            let mut hour = 0;
-                            let mut minute = 0;
-                            let mut second = 0;
-                            for time_piece in abs_time_piece.into_inner() {
-                                match time_piece.as_rule() {
-                                    Rule::HH12 | Rule::HH24 => {
-                                        hour = time_piece.as_str().parse::<i32>().unwrap();
-                                    }
-                                    Rule::MM => {
-                                        minute = time_piece.as_str().parse::<i32>().unwrap();
-                                    }
-                                    Rule::SS => {
-                                        second = time_piece.as_str().parse::<i32>().unwrap();
-                                    }
-                                    Rule::meridiem => {
-                                        if matches!(
-                                            time_piece.into_inner().next().unwrap().as_rule(),
-                                            Rule::pm
-                                        ) {
-                                            // don't change for 12pm
-                                            if hour != 12 {
-                                                hour += 12;
-                                            }
-                                        } else {
-                                            // special case 12am = midnight
-                                            if hour == 12 {
-                                                hour = 0;
-                                            }
-                                        }
-                                    }
-                                    Rule::time_special => {
-                                        if time_piece.as_str().eq_ignore_ascii_case("midnight") {
-                                            hour = 0;
-                                        } else if time_piece.as_str().eq_ignore_ascii_case("noon") {
-                                            hour = 12;
-                                        } else if time_piece.as_str().eq_ignore_ascii_case("now") {
-                                            now = true;
-                                        }
-                                    }
-                                    _ => unreachable!(),
+                            has_time = true;
+                            let (time_spec, zone) = parse_time(abs_time_piece, source, options)?;
+                            rv.time_spec = time_spec;
+                            zone_suffix = zone.or(zone_suffix);
+                        }
+                        Rule::time_range => {
+                            has_time = true;
+                            let mut times = abs_time_piece
+                                .into_inner()
+                                .map(|time| parse_time(time, source, options));
+                            let (start, start_zone) = times.next().unwrap()?;
+                            let (end, end_zone) = times.next().unwrap()?;
+                            rv.time_spec = start;
+                            rv.end_time = end;
+                            zone_suffix = match (start_zone, end_zone) {
+                                (Some(start_zone), Some(end_zone)) if start_zone != end_zone => {
+                                    return Err(DateParseError::ConflictingZone(
+                                        start_zone.to_string(),
+                                        end_zone.to_string(),
+                                    ));
                                 }
-                            }
-                            if !now {
-                                rv.time_spec = Some(TimeSpec::Abs {
-                                    hour,
-                                    minute,
-                                    second,
-                                });
-                            }
+                                (start_zone, end_zone) => start_zone.or(end_zone),
+                            };
                         }
                         Rule::date_absolute => {
                             let mut day = 0;
@@ -429,32 +2466,19 @@ fn parse_input(expr: &str) -> Result<InputExpr<'_>, DateParseError> {
                             let mut year = None;
                             for date_piece in abs_time_piece.into_inner() {
                                 match date_piece.as_rule() {
+                                    Rule::iso_date => {
+                                        let (iso_year, iso_month, iso_day) =
+                                            parse_iso_date(date_piece);
+                                        day = iso_day;
+                                        month = Some(iso_month);
+                                        year = Some(iso_year);
+                                    }
                                     Rule::english_date => {
                                         for english_piece in date_piece.into_inner() {
                                             match english_piece.as_rule() {
                                                 Rule::english_month => {
-                                                    month = Some(
-                                                        match english_piece
-                                                            .into_inner()
-                                                            .next()
-                                                            .unwrap()
-                                                            .as_rule()
-                                                        {
-                                                            Rule::m01 => 1,
-                                                            Rule::m02 => 2,
-                                                            Rule::m03 => 3,
-                                                            Rule::m04 => 4,
-                                                            Rule::m05 => 5,
-                                                            Rule::m06 => 6,
-                                                            Rule::m07 => 7,
-                                                            Rule::m08 => 8,
-                                                            Rule::m09 => 9,
-                                                            Rule::m10 => 10,
-                                                            Rule::m11 => 11,
-                                                            Rule::m12 => 12,
-                                                            _ => unreachable!(),
-                                                        },
-                                                    );
+                                                    month =
+                                                        Some(parse_english_month(english_piece));
                                                 }
                                                 Rule::english_day => {
                                                     day = english_piece.as_str()
@@ -477,85 +2501,135 @@ fn parse_input(expr: &str) -> Result<InputExpr<'_>, DateParseError> {
                                             }
                                         }
                                     }
-                                    Rule::ddmmyyyy => {
-                                        for date_piece in date_piece.into_inner() {
-                                            match date_piece.as_rule() {
-                                                Rule::dd => {
-                                                    day =
-                                                        date_piece.as_str().parse::<i32>().unwrap();
-                                                }
-                                                Rule::mm => {
-                                                    month = Some(
-                                                        date_piece.as_str().parse::<i32>().unwrap(),
-                                                    );
-                                                }
-                                                Rule::yyyy => {
-                                                    year =
-                                                        Some(date_piece.as_str().parse().unwrap());
-                                                }
-                                                _ => unreachable!(),
-                                            }
-                                        }
+                                    Rule::numeric_date => {
+                                        let (numeric_day, numeric_month, numeric_year) =
+                                            parse_numeric_date(date_piece, options.date_order)?;
+                                        day = numeric_day;
+                                        month = Some(numeric_month);
+                                        year = numeric_year;
                                     }
                                     _ => unreachable!(),
                                 }
                             }
                             rv.date_spec = Some(DateSpec::Abs { day, month, year });
                         }
+                        Rule::date_weekday => {
+                            let mut weekday = Weekday::Mon;
+                            let mut mode = WeekdayMode::This;
+                            for weekday_piece in abs_time_piece.into_inner() {
+                                match weekday_piece.as_rule() {
+                                    Rule::weekday_qualifier => {
+                                        mode = match weekday_piece
+                                            .into_inner()
+                                            .next()
+                                            .unwrap()
+                                            .as_rule()
+                                        {
+                                            Rule::next => WeekdayMode::Next,
+                                            Rule::last => WeekdayMode::Last,
+                                            Rule::this => WeekdayMode::This,
+                                            _ => unreachable!(),
+                                        };
+                                    }
+                                    Rule::weekday => {
+                                        weekday = parse_weekday(weekday_piece);
+                                    }
+                                    _ => unreachable!(),
+                                }
+                            }
+                            rv.date_spec = Some(DateSpec::Weekday { weekday, mode });
+                        }
                         Rule::date_relative => {
-                            let mut days = 0;
+                            let mut duration = CalendarDuration::default();
                             for days_piece in abs_time_piece.into_inner() {
                                 match days_piece.as_rule() {
                                     Rule::tomorrow => {
-                                        days = 1;
+                                        duration.days = 1;
                                     }
                                     Rule::yesterday => {
-                                        days = -1;
+                                        duration.days = -1;
                                     }
                                     Rule::today => {
-                                        days = 0;
+                                        duration.days = 0;
                                     }
-                                    Rule::in_days => {
-                                        days = as_int(days_piece);
+                                    Rule::in_period => {
+                                        duration = parse_rel_duration(days_piece)?;
+                                    }
+                                    Rule::period_ago => {
+                                        duration = parse_rel_duration(days_piece)?.negate();
                                     }
                                     _ => unreachable!(),
                                 }
                             }
-                            rv.date_spec = Some(DateSpec::Rel { days });
+                            rv.date_spec = Some(DateSpec::Rel(duration));
+                        }
+                        Rule::date_nth_weekday => {
+                            rv.date_spec = Some(parse_nth_weekday(abs_time_piece));
+                        }
+                        Rule::date_week => {
+                            rv.date_spec = Some(parse_week_date(abs_time_piece));
+                        }
+                        Rule::date_ordinal => {
+                            rv.date_spec = Some(parse_ordinal_date(abs_time_piece));
+                        }
+                        Rule::date_boundary => {
+                            rv.date_spec = Some(parse_boundary(abs_time_piece));
                         }
                         _ => unreachable!(),
                     }
                 }
-            }
-            Rule::rel_time | Rule::neg_rel_time => {
-                let mut hours = 0;
-                let mut minutes = 0;
-                let mut seconds = 0;
-                let is_negative = piece.as_rule() == Rule::neg_rel_time;
-                for time_piece in piece.into_inner() {
-                    match time_piece.as_rule() {
-                        Rule::rel_hours => {
-                            hours = as_int(time_piece);
-                        }
-                        Rule::rel_minutes => {
-                            minutes = as_int(time_piece);
-                        }
-                        Rule::rel_seconds => {
-                            seconds = as_int(time_piece);
+
+                // without a time a boundary refers to the very start or end
+                // of the period, any other date to the whole day.
+                if !has_time {
+                    let (hour, minute, second) = match rv.date_spec {
+                        Some(DateSpec::Boundary {
+                            boundary: Boundary::End,
+                            ..
+                        }) => (23, 59, 59),
+                        Some(DateSpec::Boundary { .. }) => (0, 0, 0),
+                        _ => {
+                            rv.end_time = Some(TimeSpec::midnight());
+                            (0, 0, 0)
                         }
+                    };
+                    rv.time_spec = Some(TimeSpec::Abs {
+                        hour,
+                        minute,
+                        second,
+                        nanosecond: 0,
+                    });
+                }
+            }
+            Rule::rel_time => {
+                rv.time_spec = Some(TimeSpec::Rel(parse_rel_duration(piece)?));
+            }
+            Rule::neg_rel_time => {
+                rv.time_spec = Some(TimeSpec::Rel(parse_rel_duration(piece)?.negate()));
+            }
+            Rule::offset | Rule::offset_before => {
+                let mut offset = CalendarDuration::default();
+                let mut negate = false;
+                for offset_piece in piece.into_inner() {
+                    match offset_piece.as_rule() {
+                        Rule::minus | Rule::before => negate = true,
+                        Rule::offset_duration => offset = parse_rel_duration(offset_piece)?,
                         _ => unreachable!(),
                     }
                 }
-                if is_negative {
-                    hours *= -1;
-                    minutes *= -1;
-                    seconds *= -1;
+                if negate {
+                    offset = offset.negate();
                 }
-                rv.time_spec = Some(TimeSpec::Rel {
-                    hours,
-                    minutes,
-                    seconds,
-                });
+                rv.offset = rv
+                    .offset
+                    .checked_add(offset)
+                    .ok_or(DateParseError::OutOfRange("offset"))?;
+            }
+            Rule::rounding => {
+                rv.rounding = Some(parse_rounding(piece)?);
+            }
+            Rule::snap => {
+                rv.time_spec = Some(TimeSpec::Snap(parse_snap(piece)));
             }
             _ => unreachable!(),
         }
@@ -569,5 +2643,300 @@ fn parse_input(expr: &str) -> Result<InputExpr<'_>, DateParseError> {
         rv.locations.insert(0, "utc");
     }
 
+    // an explicit offset on a timestamp makes the instant absolute
+    // and that offset becomes the source zone unless the location has
+    // the same offset at that instant.
+    if let Some(offset) = source_offset {
+        let offset_zone = parse_timestamp_offset(offset)?;
+        let instant = rv
+            .resolve(Utc::now().with_timezone(&offset_zone.tz()))
+            .map(|x| x.0)
+            .ok();
+        let same_offset = match (rv.locations.first().and_then(|x| find_zone(x)), instant) {
+            (Some(zone), Some(instant)) => {
+                instant.with_timezone(&zone.tz()).offset().fix() == instant.offset().fix()
+            }
+            _ => false,
+        };
+        if !same_offset {
+            rv.source_zone = Some(offset_zone);
+        }
+    }
+
+    // a zone attached to the time is the source zone.  If there is an
+    // explicit `in` location both have to agree.
+    if let Some(zone) = zone_suffix {
+        rv.zone_suffix = Some(zone);
+        if rv.locations.is_empty() || arrow {
+            rv.locations.insert(0, zone);
+        }
+    } else if arrow && !unix_time && source_offset.is_none() {
+        // `->` only ever names targets.  Without a zone on the time or the
+        // timestamp the time is in the local zone.
+        rv.locations.insert(0, "local");
+    }
+
     Ok(rv)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A Saturday morning in October, a week before the end of DST in Europe.
+    fn now() -> DateTime<Utc> {
+        Utc.ymd(2026, 10, 17).and_hms(10, 30, 0)
+    }
+
+    fn options() -> ParseOptions {
+        ParseOptions::default()
+    }
+
+    fn with_direction(direction: TimeDirection) -> ParseOptions {
+        ParseOptions {
+            direction,
+            ..ParseOptions::default()
+        }
+    }
+
+    fn with_dst_policy(dst_policy: DstPolicy) -> ParseOptions {
+        ParseOptions {
+            dst_policy,
+            ..ParseOptions::default()
+        }
+    }
+
+    fn eval_interval(
+        expr: &str,
+        options: &ParseOptions,
+    ) -> Result<IntervalAtLocation, DateParseError> {
+        let expr = InputExpr::parse_with_options(expr, options)?;
+        Ok(expr.process_interval_at(now())?.remove(0))
+    }
+
+    fn eval(expr: &str, options: &ParseOptions) -> Result<String, DateParseError> {
+        eval_interval(expr, options).map(|x| format_time(&x.start))
+    }
+
+    fn format_time(t: &TimeAtLocation) -> String {
+        t.datetime.format("%Y-%m-%d %H:%M:%S %z").to_string()
+    }
+
+    fn vienna(local: &str) -> DateTime<Zone> {
+        let local = NaiveDateTime::parse_from_str(local, "%Y-%m-%d %H:%M").unwrap();
+        localize(&Zone::Named(Tz::Europe__Vienna), &local, DstPolicy::Reject)
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn test_overflow() {
+        for expr in &[
+            "in 99999999999 hours in utc",
+            "in 99999999999999999999 minutes in utc",
+            "in 300000 years in utc",
+            "99999999999 days ago in utc",
+            "noon in utc + 99999999999 weeks",
+            "unix 99999999999999999999",
+        ] {
+            assert!(
+                matches!(eval(expr, &options()), Err(DateParseError::OutOfRange(_))),
+                "{:?} should be out of range",
+                expr
+            );
+        }
+    }
+
+    #[test]
+    fn test_dst_gap() {
+        let expr = "2:30 on 29.03.2026 in vienna";
+        let interval = eval_interval(expr, &with_dst_policy(DstPolicy::ShiftForward)).unwrap();
+        assert_eq!(format_time(&interval.start), "2026-03-29 03:30:00 +0200");
+        assert_eq!(
+            interval.start.dst_adjustment,
+            Some(DstAdjustment::Nonexistent)
+        );
+        assert_eq!(
+            eval(expr, &with_dst_policy(DstPolicy::Later)).unwrap(),
+            "2026-03-29 03:30:00 +0200"
+        );
+        assert_eq!(
+            eval(expr, &with_dst_policy(DstPolicy::Earlier)).unwrap(),
+            "2026-03-29 01:30:00 +0100"
+        );
+        assert!(matches!(
+            eval(expr, &with_dst_policy(DstPolicy::Reject)),
+            Err(DateParseError::NonexistentTime(_))
+        ));
+    }
+
+    #[test]
+    fn test_dst_overlap() {
+        let expr = "2:30 on 25.10.2026 in vienna";
+        let interval = eval_interval(expr, &with_dst_policy(DstPolicy::ShiftForward)).unwrap();
+        assert_eq!(format_time(&interval.start), "2026-10-25 02:30:00 +0200");
+        assert_eq!(
+            interval.start.dst_adjustment,
+            Some(DstAdjustment::Ambiguous)
+        );
+        assert_eq!(
+            eval(expr, &with_dst_policy(DstPolicy::Earlier)).unwrap(),
+            "2026-10-25 02:30:00 +0200"
+        );
+        assert_eq!(
+            eval(expr, &with_dst_policy(DstPolicy::Later)).unwrap(),
+            "2026-10-25 02:30:00 +0100"
+        );
+        assert!(matches!(
+            eval(expr, &with_dst_policy(DstPolicy::Reject)),
+            Err(DateParseError::AmbiguousTime(_))
+        ));
+    }
+
+    #[test]
+    fn test_apply_across_dst() {
+        let start = vienna("2026-10-24 12:00");
+        let in_days = InputExpr::parse("in 1 day").unwrap();
+        let in_hours = InputExpr::parse("in 24 hours").unwrap();
+        assert_eq!(
+            in_days.apply(start).unwrap().to_rfc3339(),
+            "2026-10-25T12:00:00+01:00"
+        );
+        assert_eq!(
+            in_hours.apply(start).unwrap().to_rfc3339(),
+            "2026-10-25T11:00:00+01:00"
+        );
+    }
+
+    #[test]
+    fn test_year_roll_over() {
+        assert_eq!(
+            eval("16.10 in utc", &options()).unwrap(),
+            "2026-10-16 00:00:00 +0000"
+        );
+        assert_eq!(
+            eval("16.10 in utc", &with_direction(TimeDirection::Future)).unwrap(),
+            "2027-10-16 00:00:00 +0000"
+        );
+        assert_eq!(
+            eval("18.10 in utc", &with_direction(TimeDirection::Past)).unwrap(),
+            "2025-10-18 00:00:00 +0000"
+        );
+        // the current day is both the next and the last occurrence
+        assert_eq!(
+            eval("17.10 in utc", &with_direction(TimeDirection::Future)).unwrap(),
+            "2026-10-17 00:00:00 +0000"
+        );
+        assert_eq!(
+            eval("17.10 in utc", &with_direction(TimeDirection::Past)).unwrap(),
+            "2026-10-17 00:00:00 +0000"
+        );
+    }
+
+    #[test]
+    fn test_weekday_roll_over() {
+        let future = with_direction(TimeDirection::Future);
+        let past = with_direction(TimeDirection::Past);
+        assert_eq!(
+            eval("saturday in utc", &future).unwrap(),
+            "2026-10-17 00:00:00 +0000"
+        );
+        assert_eq!(
+            eval("saturday 9am in utc", &future).unwrap(),
+            "2026-10-24 09:00:00 +0000"
+        );
+        assert_eq!(
+            eval("saturday 11am in utc", &past).unwrap(),
+            "2026-10-10 11:00:00 +0000"
+        );
+        assert_eq!(
+            eval("friday in utc", &future).unwrap(),
+            "2026-10-23 00:00:00 +0000"
+        );
+        assert_eq!(
+            eval("sunday in utc", &past).unwrap(),
+            "2026-10-11 00:00:00 +0000"
+        );
+        assert_eq!(
+            eval("9am in utc", &future).unwrap(),
+            "2026-10-18 09:00:00 +0000"
+        );
+        assert_eq!(
+            eval("11am in utc", &past).unwrap(),
+            "2026-10-16 11:00:00 +0000"
+        );
+    }
+
+    #[test]
+    fn test_abbreviations() {
+        assert_eq!(
+            eval("noon PST on 15.01.2027", &options()).unwrap(),
+            "2027-01-15 12:00:00 -0800"
+        );
+        assert_eq!(
+            eval("noon IST", &options()).unwrap(),
+            "2026-10-17 12:00:00 +0530"
+        );
+        let prefer_israel = ParseOptions {
+            prefer_zones: vec!["Asia/Jerusalem".into()],
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            eval("noon IST on 15.01.2027", &prefer_israel).unwrap(),
+            "2027-01-15 12:00:00 +0200"
+        );
+        assert!(matches!(
+            eval("noon CET", &options()),
+            Err(DateParseError::InactiveAbbreviation(..))
+        ));
+        assert!(matches!(
+            eval("noon PST on 15.07.2027", &options()),
+            Err(DateParseError::InactiveAbbreviation(..))
+        ));
+    }
+
+    #[test]
+    fn test_range_across_midnight() {
+        let interval = eval_interval("10pm-2am in utc", &options()).unwrap();
+        assert_eq!(format_time(&interval.start), "2026-10-17 22:00:00 +0000");
+        assert_eq!(format_time(&interval.end), "2026-10-18 02:00:00 +0000");
+
+        let interval = eval_interval("from 23:30 to 23:30 in vienna", &options()).unwrap();
+        assert_eq!(format_time(&interval.start), "2026-10-17 23:30:00 +0200");
+        assert_eq!(format_time(&interval.end), "2026-10-18 23:30:00 +0200");
+
+        // the end is on the next day in local time, across the DST change
+        let interval = eval_interval("10pm-4am on 24.10 in vienna", &options()).unwrap();
+        assert_eq!(format_time(&interval.start), "2026-10-24 22:00:00 +0200");
+        assert_eq!(format_time(&interval.end), "2026-10-25 04:00:00 +0100");
+    }
+
+    #[test]
+    fn test_duration_queries() {
+        let duration = |expr| {
+            DurationExpr::parse(expr)
+                .unwrap()
+                .unwrap()
+                .process_at(now())
+                .unwrap()
+                .duration()
+        };
+        assert_eq!(duration("until 11am in utc"), Duration::minutes(30));
+        assert_eq!(
+            duration("until 9am in utc"),
+            Duration::hours(22) + Duration::minutes(30)
+        );
+        assert_eq!(
+            duration("since 11am in utc"),
+            Duration::hours(23) + Duration::minutes(30)
+        );
+        assert_eq!(
+            duration("until today in utc"),
+            Duration::hours(13) + Duration::minutes(30)
+        );
+        assert_eq!(
+            duration("between 10pm in utc and\t 2am in utc"),
+            Duration::hours(-20)
+        );
+    }
+}
@@ -1,11 +1,12 @@
 use std::fmt;
 
-use chrono::{DateTime, Timelike};
-use chrono_tz::Tz;
+use chrono::{DateTime, Duration, NaiveTime, Offset, TimeZone, Timelike};
 use serde::{Deserialize, Serialize};
 
+use crate::zone::military_zone_letter;
+
 /// Human readable time-of-day description.
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum TimeOfDay {
     EarlyMorning,
@@ -39,18 +40,78 @@ impl fmt::Display for TimeOfDay {
     }
 }
 
+impl TimeOfDay {
+    /// Returns the time of day an hour falls into.
+    pub fn from_hour(hour: u32) -> TimeOfDay {
+        match hour {
+            5 => TimeOfDay::EarlyMorning,
+            6..=8 => TimeOfDay::Morning,
+            9..=11 => TimeOfDay::LateMorning,
+            12 => TimeOfDay::Noon,
+            13..=16 => TimeOfDay::Afternoon,
+            17..=18 => TimeOfDay::EarlyEvening,
+            19..=20 => TimeOfDay::Evening,
+            21..=22 => TimeOfDay::LateEvening,
+            23 | 0..=4 => TimeOfDay::Night,
+            24.. => unreachable!(),
+        }
+    }
+
+    /// Returns the time that input like `morning` resolves to by default.
+    pub fn default_time(self) -> NaiveTime {
+        let hour = match self {
+            TimeOfDay::EarlyMorning => 5,
+            TimeOfDay::Morning => 8,
+            TimeOfDay::LateMorning => 10,
+            TimeOfDay::Noon => 12,
+            TimeOfDay::Afternoon => 15,
+            TimeOfDay::EarlyEvening => 17,
+            TimeOfDay::Evening => 19,
+            TimeOfDay::LateEvening => 22,
+            TimeOfDay::Night => 23,
+        };
+        NaiveTime::from_hms(hour, 0, 0)
+    }
+}
+
 /// Given a datetime object returns a human readable time-of-day description.
-pub fn get_time_of_day(dt: DateTime<Tz>) -> TimeOfDay {
-    match dt.hour() {
-        5 => TimeOfDay::EarlyMorning,
-        6..=8 => TimeOfDay::Morning,
-        9..=11 => TimeOfDay::LateMorning,
-        12 => TimeOfDay::Noon,
-        13..=16 => TimeOfDay::Afternoon,
-        17..=18 => TimeOfDay::EarlyEvening,
-        19..=20 => TimeOfDay::Evening,
-        21..=22 => TimeOfDay::LateEvening,
-        23 | 0..=4 => TimeOfDay::Night,
-        24.. => unreachable!(),
+pub fn get_time_of_day<Tz: TimeZone>(dt: DateTime<Tz>) -> TimeOfDay {
+    TimeOfDay::from_hour(dt.hour())
+}
+
+/// Formats the time of a datetime in military notation (eg: `1430Z`).
+///
+/// The zone is given as NATO letter, offsets that are not full hours are
+/// appended numerically instead (`1430+0530`).
+pub fn format_military<Tz: TimeZone>(dt: &DateTime<Tz>) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    match military_zone_letter(&dt.offset().fix()) {
+        Some(letter) => format!("{}{}", dt.format("%H%M"), letter),
+        None => dt.format("%H%M%z").to_string(),
+    }
+}
+
+/// Formats a duration as exact ISO 8601 duration (eg: `PT26H30M`).
+///
+/// Days are left out on purpose as they are calendar days in relative
+/// expressions, so the result can be used with `in` again.  Fractional
+/// seconds are truncated.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.num_seconds();
+    let sign = if secs < 0 { "-" } else { "" };
+    let secs = secs.unsigned_abs();
+    let (hours, minutes, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    let mut rv = format!("{}PT", sign);
+    if hours > 0 {
+        rv.push_str(&format!("{}H", hours));
+    }
+    if minutes > 0 {
+        rv.push_str(&format!("{}M", minutes));
+    }
+    if secs > 0 || (hours == 0 && minutes == 0) {
+        rv.push_str(&format!("{}S", secs));
     }
+    rv
 }
@@ -0,0 +1,319 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use chrono::{
+    DateTime, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, Offset, TimeZone,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// A timezone which is either a named IANA zone or a fixed UTC offset.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Zone {
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+/// The offset type of [`Zone`].
+#[derive(Copy, Clone, PartialEq)]
+pub enum ZoneOffset {
+    Named(<Tz as TimeZone>::Offset),
+    Fixed(FixedOffset),
+}
+
+impl Zone {
+    /// Returns the name of the zone.
+    ///
+    /// For named zones this is the IANA name, fixed offsets are named
+    /// after their offset (eg: `UTC+05:30`).
+    pub fn name(&self) -> Cow<'static, str> {
+        match self {
+            Zone::Named(tz) => Cow::Borrowed(tz.name()),
+            Zone::Fixed(offset) => Cow::Owned(format_offset_name(offset)),
+        }
+    }
+}
+
+impl From<Tz> for Zone {
+    fn from(tz: Tz) -> Zone {
+        Zone::Named(tz)
+    }
+}
+
+impl From<FixedOffset> for Zone {
+    fn from(offset: FixedOffset) -> Zone {
+        Zone::Fixed(offset)
+    }
+}
+
+impl TimeZone for Zone {
+    type Offset = ZoneOffset;
+
+    fn from_offset(offset: &ZoneOffset) -> Zone {
+        match offset {
+            ZoneOffset::Named(offset) => Zone::Named(Tz::from_offset(offset)),
+            ZoneOffset::Fixed(offset) => Zone::Fixed(*offset),
+        }
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<ZoneOffset> {
+        match self {
+            Zone::Named(tz) => tz.offset_from_local_date(local).map(ZoneOffset::Named),
+            Zone::Fixed(offset) => LocalResult::Single(ZoneOffset::Fixed(*offset)),
+        }
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<ZoneOffset> {
+        match self {
+            Zone::Named(tz) => tz.offset_from_local_datetime(local).map(ZoneOffset::Named),
+            Zone::Fixed(offset) => LocalResult::Single(ZoneOffset::Fixed(*offset)),
+        }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> ZoneOffset {
+        match self {
+            Zone::Named(tz) => ZoneOffset::Named(tz.offset_from_utc_date(utc)),
+            Zone::Fixed(offset) => ZoneOffset::Fixed(*offset),
+        }
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> ZoneOffset {
+        match self {
+            Zone::Named(tz) => ZoneOffset::Named(tz.offset_from_utc_datetime(utc)),
+            Zone::Fixed(offset) => ZoneOffset::Fixed(*offset),
+        }
+    }
+}
+
+impl Offset for ZoneOffset {
+    fn fix(&self) -> FixedOffset {
+        match self {
+            ZoneOffset::Named(offset) => offset.fix(),
+            ZoneOffset::Fixed(offset) => *offset,
+        }
+    }
+}
+
+impl fmt::Display for ZoneOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZoneOffset::Named(offset) => fmt::Display::fmt(offset, f),
+            ZoneOffset::Fixed(offset) => fmt::Display::fmt(offset, f),
+        }
+    }
+}
+
+impl fmt::Debug for ZoneOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // this is what ends up in the serialized RFC 3339 timestamps
+        fmt::Debug::fmt(&self.fix(), f)
+    }
+}
+
+/// Controls how local times at DST transitions are resolved.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DstPolicy {
+    /// Ambiguous times pick the earlier instant, nonexistent times are
+    /// moved back by the length of the gap (2:30 becomes 1:30).
+    Earlier,
+    /// Ambiguous times pick the later instant, nonexistent times are
+    /// moved forward by the length of the gap (2:30 becomes 3:30).
+    Later,
+    /// Ambiguous and nonexistent times are rejected with an error.
+    Reject,
+    /// Ambiguous times pick the earlier instant, nonexistent times are
+    /// moved forward by the length of the gap.  This is the default.
+    ShiftForward,
+}
+
+impl Default for DstPolicy {
+    fn default() -> DstPolicy {
+        DstPolicy::ShiftForward
+    }
+}
+
+/// Indicates that a local time fell into a DST transition.
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DstAdjustment {
+    /// The local time exists twice and one of the two instants was picked.
+    Ambiguous,
+    /// The local time does not exist and was shifted.
+    Nonexistent,
+}
+
+impl fmt::Display for DstAdjustment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DstAdjustment::Ambiguous => write!(f, "ambiguous local time"),
+            DstAdjustment::Nonexistent => write!(f, "nonexistent local time"),
+        }
+    }
+}
+
+/// A datetime in a zone and the DST adjustment made to get there, if any.
+pub(crate) type Localized = (DateTime<Zone>, Option<DstAdjustment>);
+
+/// Converts a local time into a datetime in the zone according to the policy.
+///
+/// On success the adjustment is returned if one was made, if the policy
+/// rejects the local time the kind of transition is returned as error.
+pub(crate) fn localize(
+    zone: &Zone,
+    local: &NaiveDateTime,
+    policy: DstPolicy,
+) -> Result<Localized, DstAdjustment> {
+    match zone.from_local_datetime(local) {
+        LocalResult::Single(rv) => Ok((rv, None)),
+        LocalResult::Ambiguous(earlier, later) => match policy {
+            DstPolicy::Earlier | DstPolicy::ShiftForward => {
+                Ok((earlier, Some(DstAdjustment::Ambiguous)))
+            }
+            DstPolicy::Later => Ok((later, Some(DstAdjustment::Ambiguous))),
+            DstPolicy::Reject => Err(DstAdjustment::Ambiguous),
+        },
+        LocalResult::None => {
+            // the offsets around the gap.  Interpreting the local time in the
+            // offset before the gap moves it forward, the one after moves it back.
+            let probe = |days| {
+                let utc = local
+                    .checked_add_signed(Duration::days(days))
+                    .unwrap_or(*local);
+                zone.offset_from_utc_datetime(&utc).fix()
+            };
+            let offset = match policy {
+                DstPolicy::Later | DstPolicy::ShiftForward => probe(-1),
+                DstPolicy::Earlier => probe(1),
+                DstPolicy::Reject => return Err(DstAdjustment::Nonexistent),
+            };
+            let utc = local
+                .checked_sub_signed(Duration::seconds(offset.local_minus_utc() as i64))
+                .ok_or(DstAdjustment::Nonexistent)?;
+            Ok((
+                zone.from_utc_datetime(&utc),
+                Some(DstAdjustment::Nonexistent),
+            ))
+        }
+    }
+}
+
+fn format_offset_name(offset: &FixedOffset) -> String {
+    let secs = offset.local_minus_utc();
+    if secs == 0 {
+        return "UTC".into();
+    }
+    let sign = if secs < 0 { '-' } else { '+' };
+    let secs = secs.abs();
+    format!("UTC{}{:02}:{:02}", sign, secs / 3600, secs / 60 % 60)
+}
+
+/// Parses a UTC offset.
+///
+/// This accepts numeric offsets (`+HH:MM`, `+HHMM` or `+HH`) optionally
+/// prefixed with `UTC` or `GMT` in which case the hour can also be a single
+/// digit (`UTC+5:30`, `GMT-3`).  Note that unlike the POSIX style IANA
+/// names (`Etc/GMT-3`) the sign has the common meaning here.
+pub(crate) fn parse_offset(value: &str) -> Option<FixedOffset> {
+    let (value, prefixed) = match value.get(..3) {
+        Some(prefix)
+            if prefix.eq_ignore_ascii_case("utc") || prefix.eq_ignore_ascii_case("gmt") =>
+        {
+            (&value[3..], true)
+        }
+        _ => (value, false),
+    };
+    let (sign, rest) = match value.as_bytes().first()? {
+        b'+' => (1, &value[1..]),
+        b'-' => (-1, &value[1..]),
+        _ => return None,
+    };
+    if !rest.bytes().all(|x| x.is_ascii_digit() || x == b':') {
+        return None;
+    }
+    let (hours, minutes) = match rest.len() {
+        1 if prefixed => (rest, "00"),
+        2 => (rest, "00"),
+        4 if prefixed && rest.as_bytes()[1] == b':' => (&rest[..1], &rest[2..]),
+        4 => (&rest[..2], &rest[2..]),
+        5 if rest.as_bytes()[2] == b':' => (&rest[..2], &rest[3..]),
+        _ => return None,
+    };
+    if !hours
+        .bytes()
+        .chain(minutes.bytes())
+        .all(|x| x.is_ascii_digit())
+    {
+        return None;
+    }
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// The NATO military timezones as letter, code word and location.
+///
+/// The letters go from `A` (UTC+1) east to `M` (UTC+12) skipping `J` and
+/// from `N` (UTC-1) west to `Y` (UTC-12).  `Z` is UTC and `J` is the local
+/// time of the observer.
+const MILITARY_ZONES: [(char, &str, &str); 26] = [
+    ('A', "alfa", "+01:00"),
+    ('B', "bravo", "+02:00"),
+    ('C', "charlie", "+03:00"),
+    ('D', "delta", "+04:00"),
+    ('E', "echo", "+05:00"),
+    ('F', "foxtrot", "+06:00"),
+    ('G', "golf", "+07:00"),
+    ('H', "hotel", "+08:00"),
+    ('I', "india", "+09:00"),
+    ('J', "juliett", "local"),
+    ('K', "kilo", "+10:00"),
+    ('L', "lima", "+11:00"),
+    ('M', "mike", "+12:00"),
+    ('N', "november", "-01:00"),
+    ('O', "oscar", "-02:00"),
+    ('P', "papa", "-03:00"),
+    ('Q', "quebec", "-04:00"),
+    ('R', "romeo", "-05:00"),
+    ('S', "sierra", "-06:00"),
+    ('T', "tango", "-07:00"),
+    ('U', "uniform", "-08:00"),
+    ('V', "victor", "-09:00"),
+    ('W', "whiskey", "-10:00"),
+    ('X', "xray", "-11:00"),
+    ('Y', "yankee", "-12:00"),
+    ('Z', "zulu", "UTC"),
+];
+
+/// Resolves a military zone letter or code word to a location.
+pub(crate) fn military_zone(value: &str) -> Option<&'static str> {
+    let value = value.to_ascii_lowercase();
+    let word = match value.as_str() {
+        "alpha" => "alfa",
+        "juliet" => "juliett",
+        "x-ray" => "xray",
+        other => other,
+    };
+    MILITARY_ZONES
+        .iter()
+        .find(|(letter, name, _)| {
+            *name == word || word.len() == 1 && word.starts_with(letter.to_ascii_lowercase())
+        })
+        .map(|x| x.2)
+}
+
+/// Returns the military zone letter for an offset if there is one.
+pub(crate) fn military_zone_letter(offset: &FixedOffset) -> Option<char> {
+    let secs = offset.local_minus_utc();
+    if secs % 3600 != 0 {
+        return None;
+    }
+    let location = match secs / 3600 {
+        0 => "UTC".to_string(),
+        hours => format!("{:+03}:00", hours),
+    };
+    MILITARY_ZONES.iter().find(|x| x.2 == location).map(|x| x.0)
+}
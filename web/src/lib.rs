@@ -1,7 +1,10 @@
 use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
-use libwhen::TimeAtLocation;
+use libwhen::{
+    DateParseError, DurationExpr, InputExpr, IntervalAtLocation, ParseOptions, TimeAtLocation,
+    TimeDifference,
+};
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -9,33 +12,64 @@ use libwhen::TimeAtLocation;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-#[derive(Serialize)]
+#[derive(Serialize, Default)]
 pub struct ParseResult {
     is_relative: bool,
     locations: Vec<TimeAtLocation>,
+    intervals: Vec<IntervalAtLocation>,
+    duration: Option<TimeDifference>,
     error: Option<String>,
 }
 
-fn handle_expr(input: &str) -> Result<(Vec<TimeAtLocation>, bool), String> {
-    let expr = libwhen::InputExpr::parse(&input).map_err(|x| x.to_string())?;
-    Ok(expr
-        .process()
-        .map(|x| (x, expr.is_relative()))
-        .map_err(|x| x.to_string())?)
+fn handle_expr(input: &str, options: &ParseOptions) -> Result<ParseResult, DateParseError> {
+    if let Some(expr) = DurationExpr::parse_with_options(input, options)? {
+        let duration = expr.process()?;
+        return Ok(ParseResult {
+            is_relative: true,
+            locations: vec![duration.from().clone(), duration.to().clone()],
+            duration: Some(duration),
+            ..Default::default()
+        });
+    }
+    let expr = InputExpr::parse_with_options(input, options)?;
+    let intervals = expr.process_interval()?;
+    Ok(ParseResult {
+        is_relative: expr.is_relative(),
+        locations: intervals.iter().map(|x| x.start().clone()).collect(),
+        intervals: if expr.is_interval() {
+            intervals
+        } else {
+            Vec::new()
+        },
+        ..Default::default()
+    })
 }
 
 #[wasm_bindgen]
 pub fn parse_expr(input: String) -> String {
-    let (locations, is_relative, error) = match handle_expr(&input) {
-        Ok((locations, is_relative)) => (locations, is_relative, None),
-        Err(err) => (Vec::new(), false, Some(err.to_string())),
-    };
-    serde_json::to_string(&ParseResult {
-        is_relative,
-        locations,
-        error,
-    })
-    .unwrap()
+    evaluate(&input, &ParseOptions::default())
+}
+
+/// Like `parse_expr` but takes the parse options as JSON string
+/// (eg: `{"date_order": "mdy"}`).
+#[wasm_bindgen]
+pub fn parse_expr_with_options(input: String, options: String) -> String {
+    match serde_json::from_str(&options) {
+        Ok(options) => evaluate(&input, &options),
+        Err(err) => serde_json::to_string(&ParseResult {
+            error: Some(format!("invalid options: {}", err)),
+            ..Default::default()
+        })
+        .unwrap(),
+    }
+}
+
+fn evaluate(input: &str, options: &ParseOptions) -> String {
+    let rv = handle_expr(input, options).unwrap_or_else(|err| ParseResult {
+        error: Some(err.to_string()),
+        ..Default::default()
+    });
+    serde_json::to_string(&rv).unwrap()
 }
 
 #[wasm_bindgen]
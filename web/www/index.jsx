@@ -15,22 +15,31 @@ const EXAMPLES = [
   "2pm in 2 days in New Delhi",
   "now in yyz -> sfo -> vie -> lhr",
   "unix 1639067620 in Tokyo",
+  "2021-12-09T16:33:40+02:00 -> Tokyo",
+  "9am-5pm in vienna -> sfo",
+  "until 5pm friday in sfo",
+  "17.05.2026 in vienna -> tokyo -> sfo",
 ];
 
+function formatDayShift(shift) {
+  return `${shift > 0 ? "+" : ""}${shift} ${Math.abs(shift) === 1 ? "day" : "days"}`;
+}
+
 function evaluateDateExpr(input) {
   return JSON.parse(wasm.parse_expr(input || "now"));
 }
 
 function parseDate(datetime) {
-  const match = datetime.match(/^([^T]+)T([^.]+)/);
+  const match = datetime.match(/^([^T]+)T(\d{2}:\d{2}:\d{2}(?:\.\d+)?)/);
   return {
     time: match[2],
     date: match[1],
   };
 }
 
-function Location({ location: loc }) {
+function Location({ location: loc, interval }) {
   const dt = parseDate(loc.datetime);
+  const end = interval && parseDate(interval.end.datetime);
   return (
     <table>
       <tbody>
@@ -42,10 +51,21 @@ function Location({ location: loc }) {
             {loc.time_of_day.replace(/_/g, " ")})
           </td>
         </tr>
+        {interval && (
+          <tr>
+            <th>Until</th>
+            <td>
+              <span className="time">{end.time}</span> (
+              <span className="date">{end.date}</span>; {interval.duration_human})
+            </td>
+          </tr>
+        )}
         <tr>
           <th>Date</th>
           <td>
-            <span className="date">{dt.date}</span>
+            <span className="date">{dt.date}</span> ({loc.iso_week_date}; day{" "}
+            {loc.day_of_year})
+            {interval && interval.day_shift !== 0 && ` (${formatDayShift(interval.day_shift)})`}
           </td>
         </tr>
         <tr>
@@ -96,27 +116,40 @@ function Examples({setExpr}) {
   );
 }
 
-function Results({locations}) {
+function Results({locations, intervals}) {
   return (
     <ul>
       {locations.map((loc, idx) => (
         <li key={idx}>
-          <Location location={loc} />
+          <Location location={loc} interval={intervals[idx]} />
         </li>
       ))}
     </ul>
   );
 }
 
-function getTextResults(locations) {
-  return locations
-    .map((loc) => {
+function getTextResults(locations, intervals, duration) {
+  const prefix = duration
+    ? `duration: ${duration.duration_human} (${duration.duration_exact})\n\n`
+    : "";
+  return prefix + locations
+    .map((loc, idx) => {
       const dt = parseDate(loc.datetime);
       const lines = [
         `time: ${dt.time} (${loc.relative_to_now_human}; ${loc.time_of_day.replace(/_/g, " ")})`,
-        `date: ${dt.date}`,
-        `zone: ${loc.timezone.name} (${loc.timezone.abbrev}; ${loc.timezone.utc_offset})`,
       ];
+      const interval = intervals[idx];
+      if (interval) {
+        const end = parseDate(interval.end.datetime);
+        lines.push(`until: ${end.time} (${end.date}; ${interval.duration_human})`);
+      }
+      const shift = interval && interval.day_shift !== 0
+        ? ` (${formatDayShift(interval.day_shift)})`
+        : "";
+      lines.push(
+        `date: ${dt.date} (${loc.iso_week_date}; day ${loc.day_of_year})${shift}`,
+        `zone: ${loc.timezone.name} (${loc.timezone.abbrev}; ${loc.timezone.utc_offset})`,
+      );
       if (loc.location) {
         let location = `location: ${loc.location.name}`;
         if (loc.location.admin_code) {
@@ -131,7 +164,16 @@ function getTextResults(locations) {
     .join("\n\n");
 }
 
-function PlainTextResults({locations}) {
+function Duration({duration}) {
+  return (
+    <p className="duration">
+      <span className="time">{duration.duration_human}</span> (
+      {duration.duration_exact})
+    </p>
+  );
+}
+
+function PlainTextResults({locations, intervals, duration}) {
   const ref = useRef();
   return <pre ref={ref} onClick={() => {
     let range = document.createRange();
@@ -139,7 +181,7 @@ function PlainTextResults({locations}) {
     let sel = window.getSelection();
     sel.removeAllRanges();
     sel.addRange(range);
-  }}>{getTextResults(locations)}</pre>;
+  }}>{getTextResults(locations, intervals, duration)}</pre>;
 }
 
 function App() {
@@ -220,8 +262,11 @@ function App() {
         </div>
       ) : null}
       {showResults ? (asText
-        ? <PlainTextResults locations={rv.locations}/>
-        : <Results locations={rv.locations}/>) : null}
+        ? <PlainTextResults locations={rv.locations} intervals={rv.intervals} duration={rv.duration}/>
+        : <div>
+            {rv.duration && <Duration duration={rv.duration}/>}
+            <Results locations={rv.locations} intervals={rv.intervals}/>
+          </div>) : null}
       {rv.error && (
         <p className="error">
           <strong>Ugh:</strong>